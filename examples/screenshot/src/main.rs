@@ -0,0 +1,30 @@
+use rice_ui::{
+    dom::{Color, DOM, StyleProp, StyleSheet, StyleValue},
+    layout::{Layout, Monospace, Size},
+    wgpu::render_image,
+};
+
+/// Render a small DOM offscreen, and save it as `screenshot.png`
+fn main() {
+    let mut dom = DOM::new();
+
+    let mut stylesheet = StyleSheet::default();
+    stylesheet.default.insert(
+        StyleProp::BackgroundColor,
+        StyleValue::Color(Color::from_hex("#e06c75")),
+    );
+    let child = dom.insert(Layout::new(Size::Fixed(50), Size::Fixed(50)), stylesheet);
+    dom.root = dom.insert_with_children(
+        Layout::new(Size::Fixed(200), Size::Fixed(100)),
+        StyleSheet::default(),
+        vec![child],
+    );
+    dom.compute_layout(dom.root, &Monospace::new(8, 16));
+
+    let image = pollster::block_on(render_image(&mut dom, [400, 200], 2.0))
+        .expect("Failed to render offscreen");
+    image
+        .save_png("screenshot.png")
+        .expect("Failed to save screenshot");
+    println!("Saved screenshot.png ({}x{})", image.width, image.height);
+}
@@ -0,0 +1,510 @@
+//! CPU software rasterizer, for rendering without a GPU.
+//!
+//! Draws the same elements as the WGPU renderer, with the same shading & blending: colors are
+//! blended in linear space with `src + dst * (1 - src.a)`, then encoded as sRGB.
+
+mod shader;
+
+use std::{
+    collections::{HashMap, HashSet},
+    convert::Infallible,
+};
+
+use rice_dom::{ComputedStyle, DOM};
+use rice_layout::Rect;
+use rice_render::{Image, Offscreen, Renderer, clip_rect, fit_image};
+
+use crate::shader::{shade, shade_image, shade_shadow};
+
+/// Buffer of linear RGBA pixels to draw to
+#[derive(Debug, Clone, PartialEq)]
+pub struct Canvas {
+    /// Physical size
+    size: [u32; 2],
+    pixels: Vec<[f32; 4]>,
+}
+
+impl Canvas {
+    /// Transparent canvas of the given physical size
+    pub fn new(size: [u32; 2]) -> Self {
+        Self {
+            size,
+            pixels: vec![[0.0; 4]; (size[0] * size[1]) as usize],
+        }
+    }
+
+    pub fn size(&self) -> [u32; 2] {
+        self.size
+    }
+
+    /// Pixels encoded as sRGB
+    pub fn image(&self) -> Image {
+        let pixels = self
+            .pixels
+            .iter()
+            .flat_map(|&[r, g, b, a]| [srgb(r), srgb(g), srgb(b), unorm(a)])
+            .collect();
+
+        Image {
+            width: self.size[0],
+            height: self.size[1],
+            pixels,
+        }
+    }
+
+    /// Fill a range of physical pixels, blending over them the color returned for their center
+    fn fill(
+        &mut self,
+        [x0, x1]: [u32; 2],
+        [y0, y1]: [u32; 2],
+        shade: impl Fn([f32; 2]) -> [f32; 4],
+    ) {
+        for y in y0..y1 {
+            let row = (y * self.size[0]) as usize;
+            for x in x0..x1 {
+                let color = shade([x as f32 + 0.5, y as f32 + 0.5]);
+                let pixel = &mut self.pixels[row + x as usize];
+                // Clamped as when stored in the render target
+                for c in 0..4 {
+                    pixel[c] = (color[c] + pixel[c] * (1.0 - color[3])).clamp(0.0, 1.0);
+                }
+            }
+        }
+    }
+}
+
+/// Software renderer, drawing the prepared elements to a canvas
+#[derive(Debug, Clone)]
+pub struct CpuRenderer {
+    /// Physical size of the screen
+    size: [u32; 2],
+    /// Logical to physical scaling factor
+    scale: f32,
+    /// Elements to draw, in drawing order, with the physical rectangle they are clipped to
+    /// (see `clip_rect`)
+    elements: Vec<(Rect, ComputedStyle, Option<[u32; 4]>)>,
+    /// Images drawn by the elements, by source, or none for images that could not be loaded
+    images: HashMap<String, Option<Image>>,
+    /// Sources whose images were loaded from files
+    files: HashSet<String>,
+}
+
+impl Default for CpuRenderer {
+    fn default() -> Self {
+        Self {
+            size: [1, 1],
+            scale: 1.0,
+            elements: Vec::new(),
+            images: HashMap::new(),
+            files: HashSet::new(),
+        }
+    }
+}
+
+impl CpuRenderer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a decoded image for a source, drawn by the elements with this image source instead
+    /// of the PNG file at this path
+    pub fn insert_image(&mut self, source: impl Into<String>, image: Image) {
+        let source = source.into();
+        self.files.remove(&source);
+        self.images.insert(source, Some(image));
+    }
+}
+
+impl Renderer for CpuRenderer {
+    type Context<'a> = ();
+    type Target<'a> = Canvas;
+
+    fn resize(&mut self, _: (), size: [u32; 2], scale: f32) {
+        self.size = size;
+        self.scale = scale;
+    }
+
+    fn prepare(&mut self, _: (), dom: &DOM) {
+        // Images are decoded from PNG files on first use
+        if dom.reload_images {
+            for source in self.files.drain() {
+                self.images.remove(&source);
+            }
+        }
+        for &index in &dom.redraw {
+            if let Some(source) = &dom.styles[index].image
+                && !self.images.contains_key(source)
+            {
+                let image = Image::open_png(source).ok();
+                self.images.insert(source.clone(), image);
+                self.files.insert(source.clone());
+            }
+        }
+
+        self.elements.clear();
+        self.elements.extend(dom.redraw.iter().map(|&index| {
+            let clip = clip_rect(dom, index, self.size, self.scale);
+            (dom.rects[index].clone(), dom.styles[index].clone(), clip)
+        }));
+    }
+
+    fn draw(&self, canvas: &mut Canvas) {
+        // Clip to both the screen and the canvas
+        let width = self.size[0].min(canvas.size[0]);
+        let height = self.size[1].min(canvas.size[1]);
+
+        for (rect, style, clip) in &self.elements {
+            // Columns & rows of pixels the element can cover
+            let [x, y, w, h] = clip.unwrap_or([0, 0, width, height]);
+            let columns = [x.min(width), (x + w).min(width)];
+            let rows = [y.min(height), (y + h).min(height)];
+
+            let offset = rect.position.map(|p| p as f32 * self.scale);
+            let size = rect.size.map(|s| s as f32 * self.scale);
+
+            // Shadow beneath the element, fading out over 3 standard deviations of the blur
+            let shadow = &style.shadow;
+            if shadow.color.a > 0.0 {
+                let padding = (shadow.spread + 1.5 * shadow.blur + 1.0) * self.scale;
+                let origin = [0, 1].map(|i| offset[i] + shadow.offset[i] * self.scale);
+
+                canvas.fill(
+                    span(origin[0] - padding, origin[0] + size[0] + padding, columns),
+                    span(origin[1] - padding, origin[1] + size[1] + padding, rows),
+                    |center| {
+                        let local = [center[0] - origin[0], center[1] - origin[1]];
+                        shade_shadow(style, local, size, self.scale)
+                    },
+                );
+            }
+
+            canvas.fill(
+                span(offset[0], offset[0] + size[0], columns),
+                span(offset[1], offset[1] + size[1], rows),
+                |center| {
+                    let local = [center[0] - offset[0], center[1] - offset[1]];
+                    shade(style, local, size, self.scale)
+                },
+            );
+
+            // Image above the element
+            let image = style
+                .image
+                .as_ref()
+                .and_then(|source| self.images.get(source));
+            if let Some(Some(image)) = image {
+                let logical = rect.size.map(|s| s as f32);
+                let placement = fit_image(style.image_fit, [image.width, image.height], logical);
+                let [x, y, w, h] = placement.rect.map(|v| v * self.scale);
+                let [left, top, right, bottom] = placement.uv;
+
+                canvas.fill(
+                    span(offset[0] + x, offset[0] + x + w, columns),
+                    span(offset[1] + y, offset[1] + y + h, rows),
+                    |center| {
+                        let local = [center[0] - offset[0], center[1] - offset[1]];
+                        let uv = [
+                            left + (right - left) * (local[0] - x) / w,
+                            top + (bottom - top) * (local[1] - y) / h,
+                        ];
+                        shade_image(style, image, uv, local, size, self.scale)
+                    },
+                );
+            }
+        }
+    }
+}
+
+impl Offscreen for CpuRenderer {
+    type Error = Infallible;
+
+    fn render_image(
+        &mut self,
+        dom: &mut DOM,
+        size: [u32; 2],
+        scale: f32,
+    ) -> Result<Image, Infallible> {
+        let size = [size[0].max(1), size[1].max(1)];
+
+        dom.dirty.push(dom.root);
+        dom.compute_redraw();
+        self.resize((), size, scale);
+        self.prepare((), dom);
+        dom.dirty.clear();
+        dom.redraw.clear();
+        dom.reload_images = false;
+
+        let mut canvas = Canvas::new(size);
+        self.draw(&mut canvas);
+        Ok(canvas.image())
+    }
+}
+
+/// Range of physical pixels covered along an axis by a physical span, clamped to the given
+/// range of pixels. Pixels are covered when their center is inside the span, as with GPU
+/// rasterization.
+fn span(start: f32, end: f32, [min, max]: [u32; 2]) -> [u32; 2] {
+    let first = (start - 0.5).ceil().clamp(min as f32, max as f32) as u32;
+    let last = (end - 0.5).ceil().clamp(min as f32, max as f32) as u32;
+
+    [first, last.max(first)]
+}
+
+/// Encode a linear color channel as 8-bit sRGB
+fn srgb(linear: f32) -> u8 {
+    let linear = linear.clamp(0.0, 1.0);
+    let encoded = if linear <= 0.0031308 {
+        linear * 12.92
+    } else {
+        1.055 * linear.powf(1.0 / 2.4) - 0.055
+    };
+    unorm(encoded)
+}
+
+/// Encode a channel in [0, 1] as 8 bits
+fn unorm(value: f32) -> u8 {
+    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use rice_dom::{
+        Color, ColorStop, Gradient, GradientKind, ImageFit, Shadow, StyleProp, StyleSheet,
+        StyleValue,
+    };
+    use rice_layout::{Layout, Monospace, Size};
+
+    use super::*;
+
+    /// Linear color
+    fn rgba(r: f32, g: f32, b: f32, a: f32) -> Color {
+        Color { r, g, b, a }
+    }
+
+    /// Stylesheet with the given default styles
+    fn styles(styles: impl IntoIterator<Item = (StyleProp, StyleValue)>) -> StyleSheet {
+        let mut stylesheet = StyleSheet::default();
+        stylesheet.default.extend(styles);
+        stylesheet
+    }
+
+    /// Stylesheet with a background color
+    fn background(color: Color) -> StyleSheet {
+        styles([(StyleProp::BackgroundColor, StyleValue::Color(color))])
+    }
+
+    /// Fixed-size layout
+    fn fixed(width: i32, height: i32) -> Layout {
+        Layout::new(Size::Fixed(width), Size::Fixed(height))
+    }
+
+    /// Lay out a DOM from the given root node, and render it with the given renderer
+    fn render(
+        renderer: &mut CpuRenderer,
+        dom: &mut DOM,
+        root: usize,
+        size: [u32; 2],
+        scale: f32,
+    ) -> Image {
+        dom.root = root;
+        dom.compute_layout(root, &Monospace::new(10, 20));
+        renderer.render_image(dom, size, scale).unwrap()
+    }
+
+    #[test]
+    fn test_fill_scaled() {
+        let mut dom = DOM::new();
+        let child = dom.insert(fixed(10, 10), background(rgba(1.0, 0.0, 0.0, 1.0)));
+        let root = dom.insert_with_children(fixed(40, 20), StyleSheet::default(), vec![child]);
+        let image = render(&mut CpuRenderer::new(), &mut dom, root, [80, 40], 2.0);
+
+        assert_eq!((image.width, image.height), (80, 40));
+        assert_eq!(image.pixel(0, 0), [255, 0, 0, 255]);
+        assert_eq!(image.pixel(19, 19), [255, 0, 0, 255]);
+        assert_eq!(image.pixel(20, 0), [0, 0, 0, 0]);
+        assert_eq!(image.pixel(0, 20), [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_scroll_clip() {
+        let mut dom = DOM::new();
+        let child = dom.insert(fixed(30, 30), background(rgba(1.0, 0.0, 0.0, 1.0)));
+        let list = dom.insert_with_children(
+            fixed(20, 10).scroll(false, true),
+            StyleSheet::default(),
+            vec![child],
+        );
+        let root = dom.insert_with_children(fixed(40, 40), StyleSheet::default(), vec![list]);
+        let image = render(&mut CpuRenderer::new(), &mut dom, root, [80, 80], 2.0);
+
+        // Children of scroll containers are clipped to them, along both axes
+        assert_eq!(image.pixel(39, 19), [255, 0, 0, 255]);
+        assert_eq!(image.pixel(40, 0), [0, 0, 0, 0]);
+        assert_eq!(image.pixel(0, 20), [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_blending() {
+        let mut dom = DOM::new();
+        let child = dom.insert(fixed(10, 10), background(rgba(0.0, 0.0, 0.0, 0.5)));
+        let white = background(rgba(1.0, 1.0, 1.0, 1.0));
+        let root = dom.insert_with_children(fixed(10, 10), white, vec![child]);
+        let image = render(&mut CpuRenderer::new(), &mut dom, root, [10, 10], 1.0);
+
+        // Half of linear white, encoded as sRGB
+        assert_eq!(image.pixel(5, 5), [188, 188, 188, 255]);
+    }
+
+    #[test]
+    fn test_rounded_corners() {
+        let mut dom = DOM::new();
+        let stylesheet = styles([
+            (
+                StyleProp::BackgroundColor,
+                StyleValue::Color(rgba(1.0, 1.0, 1.0, 1.0)),
+            ),
+            (
+                StyleProp::BorderRadius,
+                StyleValue::Corners([10.0, 0.0, 0.0, 0.0]),
+            ),
+        ]);
+        let root = dom.insert(fixed(20, 20), stylesheet);
+        let image = render(&mut CpuRenderer::new(), &mut dom, root, [20, 20], 1.0);
+
+        // Only the top left corner is rounded
+        assert_eq!(image.pixel(0, 0), [0, 0, 0, 0]);
+        assert_eq!(image.pixel(19, 0), [255, 255, 255, 255]);
+        assert_eq!(image.pixel(10, 10), [255, 255, 255, 255]);
+
+        // Anti-aliased edge
+        let edge = image.pixel(3, 2)[3];
+        assert!(edge > 0 && edge < 255);
+    }
+
+    #[test]
+    fn test_border() {
+        let mut dom = DOM::new();
+        let stylesheet = styles([
+            (
+                StyleProp::BackgroundColor,
+                StyleValue::Color(rgba(1.0, 1.0, 1.0, 1.0)),
+            ),
+            (
+                StyleProp::BorderWidth,
+                StyleValue::Sides([0.0, 0.0, 0.0, 2.0]),
+            ),
+            (
+                StyleProp::BorderColor,
+                StyleValue::Color(rgba(1.0, 0.0, 0.0, 1.0)),
+            ),
+        ]);
+        let root = dom.insert(fixed(10, 10), stylesheet);
+        let image = render(&mut CpuRenderer::new(), &mut dom, root, [10, 10], 1.0);
+
+        // Left border only
+        assert_eq!(image.pixel(1, 5), [255, 0, 0, 255]);
+        assert_eq!(image.pixel(2, 5), [255, 255, 255, 255]);
+        assert_eq!(image.pixel(9, 0), [255, 255, 255, 255]);
+    }
+
+    /// Stylesheet with a gradient from opaque black to opaque red
+    fn gradient(kind: GradientKind) -> StyleSheet {
+        let stop = |r, position| ColorStop {
+            color: rgba(r, 0.0, 0.0, 1.0),
+            position,
+        };
+        let gradient = Gradient {
+            kind,
+            stops: vec![stop(0.0, 0.0), stop(1.0, 1.0)],
+        };
+        styles([(
+            StyleProp::BackgroundGradient,
+            StyleValue::Gradient(gradient),
+        )])
+    }
+
+    #[test]
+    fn test_linear_gradient() {
+        let mut dom = DOM::new();
+        let root = dom.insert(fixed(10, 2), gradient(GradientKind::Linear { angle: 90.0 }));
+        let image = render(&mut CpuRenderer::new(), &mut dom, root, [10, 2], 1.0);
+
+        // From left to right, increasing for each pixel
+        let reds = (0..10).map(|x| image.pixel(x, 1)[0]).collect::<Vec<_>>();
+        assert!(reds.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(image.pixel(0, 0), image.pixel(0, 1));
+    }
+
+    #[test]
+    fn test_radial_gradient() {
+        let mut dom = DOM::new();
+        let kind = GradientKind::Radial {
+            center: [0.5, 0.5],
+            radius: 10.0,
+        };
+        let root = dom.insert(fixed(20, 20), gradient(kind));
+        let image = render(&mut CpuRenderer::new(), &mut dom, root, [40, 40], 2.0);
+
+        // Increasing from the center, symmetrically
+        let reds = (20..40).map(|x| image.pixel(x, 20)[0]).collect::<Vec<_>>();
+        assert!(reds.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(image.pixel(10, 20), image.pixel(29, 20));
+        assert_eq!(image.pixel(20, 10), image.pixel(20, 29));
+
+        // The last stop extends past the radius
+        assert_eq!(image.pixel(0, 0), [255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn test_image_contain() {
+        let mut dom = DOM::new();
+        let stylesheet = styles([
+            (StyleProp::Image, StyleValue::Image("flag".to_string())),
+            (StyleProp::ImageFit, StyleValue::Fit(ImageFit::Contain)),
+        ]);
+        let root = dom.insert(fixed(10, 10), stylesheet);
+
+        // Red & blue halves
+        let mut renderer = CpuRenderer::new();
+        renderer.insert_image(
+            "flag",
+            Image {
+                width: 2,
+                height: 1,
+                pixels: vec![255, 0, 0, 255, 0, 0, 255, 255],
+            },
+        );
+        let image = render(&mut renderer, &mut dom, root, [10, 10], 1.0);
+
+        // Centered vertically, with the edges repeated
+        assert_eq!(image.pixel(0, 5), [255, 0, 0, 255]);
+        assert_eq!(image.pixel(9, 5), [0, 0, 255, 255]);
+        assert_eq!(image.pixel(5, 0), [0, 0, 0, 0]);
+        assert_eq!(image.pixel(5, 9), [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_shadow() {
+        let mut dom = DOM::new();
+        let shadow = Shadow {
+            offset: [10.0, 0.0],
+            blur: 2.0,
+            spread: 0.0,
+            color: rgba(0.0, 0.0, 0.0, 1.0),
+        };
+        let child = dom.insert(
+            fixed(10, 10),
+            styles([(StyleProp::BoxShadow, StyleValue::Shadow(shadow))]),
+        );
+        let root = dom.insert_with_children(fixed(30, 10), StyleSheet::default(), vec![child]);
+        let image = render(&mut CpuRenderer::new(), &mut dom, root, [30, 10], 1.0);
+
+        // Opaque inside the offset box, fading out past its edges
+        assert_eq!(image.pixel(15, 5)[3], 255);
+        let edge = image.pixel(20, 5)[3];
+        assert!(edge > 0 && edge < 255);
+        assert_eq!(image.pixel(29, 5)[3], 0);
+        // Transparent element above its shadow
+        assert_eq!(image.pixel(5, 5)[3], 0);
+    }
+}
@@ -0,0 +1,199 @@
+//! Shading of the pixels of an element, mirroring the fragment shader of the WGPU renderer
+
+use rice_dom::{Color, ComputedStyle, Gradient, GradientKind};
+use rice_render::Image;
+
+/// Color of the pixel at a physical position within an element of the given physical size
+pub fn shade(style: &ComputedStyle, local: [f32; 2], size: [f32; 2], scale: f32) -> [f32; 4] {
+    let half = [size[0] / 2.0, size[1] / 2.0];
+    let p = [local[0] - half[0], local[1] - half[1]];
+    let radius = style.border_radius.map(|r| r * scale);
+
+    // Anti-aliased coverage of the pixel
+    let coverage = (0.5 - rounded_box(p, half, radius)).clamp(0.0, 1.0);
+
+    // Box inside the borders, with corner radii reduced by the widths of the adjacent sides
+    let [top, right, bottom, left] = style.border_width.map(|w| w * scale);
+    let inner_half = [
+        (half[0] - (left + right) / 2.0).max(0.0),
+        (half[1] - (top + bottom) / 2.0).max(0.0),
+    ];
+    let inner_center = [(left - right) / 2.0, (top - bottom) / 2.0];
+    let adjacent = [
+        left.max(top),
+        top.max(right),
+        right.max(bottom),
+        bottom.max(left),
+    ];
+    let inner_radius = [0, 1, 2, 3].map(|i| (radius[i] - adjacent[i]).max(0.0));
+    let inner_p = [p[0] - inner_center[0], p[1] - inner_center[1]];
+    let inner = (0.5 - rounded_box(inner_p, inner_half, inner_radius)).clamp(0.0, 1.0);
+
+    let background = match &style.background_gradient {
+        Some(gradient) if !gradient.stops.is_empty() => {
+            shade_gradient(gradient, p, local, size, scale)
+        }
+        _ => rgba(&style.background_color),
+    };
+    let color = if [top, right, bottom, left].iter().any(|&w| w > 0.0) {
+        let border = rgba(&style.border_color);
+        [0, 1, 2, 3].map(|i| border[i] + (background[i] - border[i]) * inner)
+    } else {
+        background
+    };
+
+    color.map(|c| c * coverage)
+}
+
+/// Color of the pixel at a physical position within the shadow of an element of the given
+/// physical size, relative to the element moved by the shadow offset
+pub fn shade_shadow(
+    style: &ComputedStyle,
+    local: [f32; 2],
+    size: [f32; 2],
+    scale: f32,
+) -> [f32; 4] {
+    let spread = style.shadow.spread * scale;
+    let size = size.map(|s| (s + 2.0 * spread).max(0.0));
+    let half = [size[0] / 2.0, size[1] / 2.0];
+    let p = [local[0] + spread - half[0], local[1] + spread - half[1]];
+    let radius = style.border_radius.map(|r| {
+        if r > 0.0 {
+            (r * scale + spread).max(0.0)
+        } else {
+            0.0
+        }
+    });
+
+    // Rounded box convolved with a gaussian, approximated from its signed distance
+    let sigma = (style.shadow.blur * scale / 2.0).max(0.5);
+    let d = rounded_box(p, half, radius);
+    let alpha = 0.5 - 0.5 * erf(d / (sigma * std::f32::consts::SQRT_2));
+
+    rgba(&style.shadow.color).map(|c| c * alpha)
+}
+
+/// Color of the pixel at a physical position within an element of the given physical size,
+/// for its image sampled at a position given as fractions of the image size
+pub fn shade_image(
+    style: &ComputedStyle,
+    image: &Image,
+    uv: [f32; 2],
+    local: [f32; 2],
+    size: [f32; 2],
+    scale: f32,
+) -> [f32; 4] {
+    let half = [size[0] / 2.0, size[1] / 2.0];
+    let p = [local[0] - half[0], local[1] - half[1]];
+    let radius = style.border_radius.map(|r| r * scale);
+    let coverage = (0.5 - rounded_box(p, half, radius)).clamp(0.0, 1.0);
+
+    // Tinted image, with premultiplied alpha
+    let tint = style.image_tint.as_ref().map_or([1.0; 4], rgba);
+    let texel = sample(image, uv);
+    let [r, g, b, a] = [0, 1, 2, 3].map(|i| texel[i] * tint[i]);
+    [r * a, g * a, b * a, a].map(|c| c * coverage)
+}
+
+/// Bilinear sample of an image, with its edge pixels repeated outside of it
+fn sample(image: &Image, uv: [f32; 2]) -> [f32; 4] {
+    let x = uv[0] * image.width as f32 - 0.5;
+    let y = uv[1] * image.height as f32 - 0.5;
+    let [fx, fy] = [x - x.floor(), y - y.floor()];
+    let texel = |x: f32, y: f32| {
+        let x = x.clamp(0.0, image.width as f32 - 1.0) as u32;
+        let y = y.clamp(0.0, image.height as f32 - 1.0) as u32;
+        let [r, g, b, a] = image.pixel(x, y);
+        [linear(r), linear(g), linear(b), a as f32 / 255.0]
+    };
+
+    let [top_left, top_right] = [
+        texel(x.floor(), y.floor()),
+        texel(x.floor() + 1.0, y.floor()),
+    ];
+    let [bottom_left, bottom_right] = [
+        texel(x.floor(), y.floor() + 1.0),
+        texel(x.floor() + 1.0, y.floor() + 1.0),
+    ];
+    [0, 1, 2, 3].map(|i| {
+        let top = top_left[i] + (top_right[i] - top_left[i]) * fx;
+        let bottom = bottom_left[i] + (bottom_right[i] - bottom_left[i]) * fx;
+        top + (bottom - top) * fy
+    })
+}
+
+/// Decode an 8-bit sRGB color channel
+fn linear(encoded: u8) -> f32 {
+    let encoded = encoded as f32 / 255.0;
+    if encoded <= 0.04045 {
+        encoded / 12.92
+    } else {
+        ((encoded + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Color of a gradient at a physical position within an element, relative to its center (`p`)
+/// and to its top left corner (`local`)
+fn shade_gradient(
+    gradient: &Gradient,
+    p: [f32; 2],
+    local: [f32; 2],
+    size: [f32; 2],
+    scale: f32,
+) -> [f32; 4] {
+    // Position along the gradient
+    let t = match gradient.kind {
+        GradientKind::Linear { angle } => {
+            // Gradient line through the center, long enough for the corners to reach its ends
+            let angle = angle.to_radians();
+            let direction = [angle.sin(), -angle.cos()];
+            let length = (size[0] * direction[0]).abs() + (size[1] * direction[1]).abs();
+            (p[0] * direction[0] + p[1] * direction[1]) / length + 0.5
+        }
+        GradientKind::Radial { center, radius } => {
+            let center = [center[0] * size[0], center[1] * size[1]];
+            (local[0] - center[0]).hypot(local[1] - center[1]) / (radius * scale)
+        }
+    };
+
+    let mut color = rgba(&gradient.stops[0].color);
+    for stops in gradient.stops.windows(2) {
+        let (start, end) = (&stops[0], &stops[1]);
+        if t > start.position {
+            let f =
+                ((t - start.position) / (end.position - start.position).max(1e-6)).clamp(0.0, 1.0);
+            let [start, end] = [rgba(&start.color), rgba(&end.color)];
+            color = [0, 1, 2, 3].map(|i| start[i] + (end[i] - start[i]) * f);
+        }
+    }
+    color
+}
+
+/// Approximation of the error function, with a max error of 5e-4
+fn erf(x: f32) -> f32 {
+    let a = x.abs();
+    let t = 1.0 + (0.278393 + (0.230389 + 0.078108 * a * a) * a) * a;
+    let r = t * t;
+    (1.0 - 1.0 / (r * r)).copysign(x)
+}
+
+/// Channels of a color
+fn rgba(color: &Color) -> [f32; 4] {
+    [color.r, color.g, color.b, color.a]
+}
+
+/// Signed distance from a point to a rounded box centered on the origin
+fn rounded_box(p: [f32; 2], half: [f32; 2], radius: [f32; 4]) -> f32 {
+    // Radius of the corner in the quadrant of the point
+    let r = match (p[0] > 0.0, p[1] > 0.0) {
+        (false, false) => radius[0],
+        (true, false) => radius[1],
+        (true, true) => radius[2],
+        (false, true) => radius[3],
+    };
+    let r = r.min(half[0]).min(half[1]);
+
+    let q = [p[0].abs() - half[0] + r, p[1].abs() - half[1] + r];
+    let outside = q[0].max(0.0).hypot(q[1].max(0.0));
+    outside + q[0].max(q[1]).min(0.0) - r
+}
@@ -1,8 +1,11 @@
 //! DOM structure to manage UI elements
 
-use rice_layout::{Layout, Rect, compute_layout};
+use rice_layout::{Layout, Measure, Rect, compute_layout, compute_positions};
 
-use crate::{ComputedStyle, StyleSheet, mouse::recurse_mouse};
+use crate::{
+    ComputedStyle, StyleSheet,
+    mouse::{recurse_mouse, recurse_scroll},
+};
 
 /// Main arena DOM
 #[derive(Debug)]
@@ -16,10 +19,14 @@ pub struct DOM {
     pub rects: Vec<Rect>,
     /// Children indices for each node
     pub children: Vec<Vec<usize>>,
+    /// Parent index for each node (none for root nodes)
+    pub parents: Vec<Option<usize>>,
     /// Computed styles from style rules (ready to be written to a buffer)
     pub styles: Vec<ComputedStyle>,
     /// Style rules for each node
     pub stylesheets: Vec<StyleSheet>,
+    /// Class name of each node, for nodes parsed from source files (empty for the others)
+    pub classes: Vec<String>,
 
     /// Mouse position
     pub mouse: [i32; 2],
@@ -28,10 +35,15 @@ pub struct DOM {
     /// Clicked state
     pub clicked: Option<usize>,
 
+    /// Nodes whose layout rules changed, that must be laid out again with their children
+    pub relayout: Vec<usize>,
     /// Dirty nodes that must be redrawn with their children
     pub dirty: Vec<usize>,
     /// Nodes that need to be redrawn, in ascending z-index (computed from dirty)
     pub redraw: Vec<usize>,
+    /// Whether the images loaded from files must be loaded again by the renderers, as they
+    /// may have changed (e.g. after a hot reload)
+    pub reload_images: bool,
 }
 
 impl DOM {
@@ -43,20 +55,88 @@ impl DOM {
             layouts: Vec::new(),
             rects: Vec::new(),
             children: Vec::new(),
+            parents: Vec::new(),
             styles: Vec::new(),
             stylesheets: Vec::new(),
+            classes: Vec::new(),
 
             mouse: [-1, -1],
             hovered: None,
             clicked: None,
+            relayout: Vec::new(),
             dirty: Vec::new(),
             redraw: Vec::new(),
+            reload_images: false,
         }
     }
 
-    /// Recompute the entire layout starting from the given root node
-    pub fn compute_layout(&mut self, root: usize) {
-        compute_layout(root, &self.layouts, &mut self.rects, &self.children);
+    /// Recompute the entire layout starting from the given root node,
+    /// measuring text with the given glyph metrics
+    pub fn compute_layout(&mut self, root: usize, measure: &dyn Measure) {
+        compute_layout(
+            root,
+            &self.layouts,
+            &mut self.rects,
+            &self.children,
+            measure,
+        );
+    }
+
+    /// Replace the layout rules of a node, and mark it for relayout if they changed
+    pub fn set_layout(&mut self, index: usize, layout: Layout) {
+        if self.layouts[index] != layout {
+            self.layouts[index] = layout;
+            self.relayout.push(index);
+        }
+    }
+
+    /// Recompute the layout of the subtrees marked for relayout only,
+    /// measuring text with the given glyph metrics
+    ///
+    /// Each subtree is laid out from the parent of its root, which resolves expand, percent,
+    /// stretched & anchored sizes and positions its siblings. While the size of the recomputed
+    /// node changes, the layout of its own parent is recomputed as well.
+    /// If nodes outside of the recomputed subtree are anchored to a node inside of it, the entire
+    /// layout is recomputed instead, so that they follow their target.
+    /// The topmost recomputed subtrees are marked dirty to be redrawn.
+    pub fn compute_relayout(&mut self, measure: &dyn Measure) {
+        let relayout = std::mem::take(&mut self.relayout);
+
+        for &index in &relayout {
+            // Subtrees of other relayout nodes are recomputed with them
+            if self.ancestors(index).any(|idx| relayout.contains(&idx)) {
+                continue;
+            }
+
+            let mut node = self.parents[index].unwrap_or(index);
+            loop {
+                let size = self.rects[node].size;
+                self.compute_layout(node, measure);
+
+                match self.parents[node] {
+                    Some(parent) if self.rects[node].size != size => node = parent,
+                    _ => break,
+                }
+            }
+
+            // Nodes anchored to the subtree from outside of it are positioned by the root
+            let inside = |idx: usize| idx == node || self.ancestors(idx).any(|idx| idx == node);
+            let anchored = self.layouts.iter().enumerate().any(|(idx, layout)| {
+                let target = layout.anchor.and_then(|anchor| anchor.target);
+                !inside(idx) && target.is_some_and(inside)
+            });
+            if anchored {
+                self.compute_layout(self.root, measure);
+                self.dirty.push(self.root);
+                return;
+            }
+            self.dirty.push(node);
+        }
+    }
+
+    /// Iterate over the ancestors of a node, from its parent up to the root
+    pub fn ancestors(&self, index: usize) -> impl Iterator<Item = usize> + '_ {
+        std::iter::successors(self.parents[index], |&idx| self.parents[idx])
     }
 
     /// Insert a root node into the arena
@@ -66,8 +146,10 @@ impl DOM {
         self.layouts.push(layout);
         self.rects.push(Rect::default());
         self.children.push(vec![]);
+        self.parents.push(None);
         self.stylesheets.push(stylesheet);
         self.styles.push(style);
+        self.classes.push(String::new());
         self.layouts.len() - 1
     }
 
@@ -79,6 +161,9 @@ impl DOM {
         children: Vec<usize>,
     ) -> usize {
         let index = self.insert(layout, style);
+        for &child in &children {
+            self.parents[child] = Some(index);
+        }
         self.children[index] = children;
         index
     }
@@ -87,9 +172,18 @@ impl DOM {
     pub fn insert_child(&mut self, layout: Layout, style: StyleSheet, parent: usize) -> usize {
         let index = self.insert(layout, style);
         self.children[parent].push(index);
+        self.parents[index] = Some(parent);
         index
     }
 
+    /// Append nodes already in the arena to the children of a node
+    pub fn append_children(&mut self, parent: usize, children: Vec<usize>) {
+        for &child in &children {
+            self.parents[child] = Some(parent);
+        }
+        self.children[parent].extend(children);
+    }
+
     /// Handle mouse position movement
     pub fn handle_mouse_moved(&mut self, mouse: [i32; 2]) {
         self.mouse = mouse;
@@ -100,7 +194,13 @@ impl DOM {
         }
 
         // 2. Else, get the index of the newly hovered node
-        let index = recurse_mouse(self.root, &self.rects, &self.children, &mouse);
+        let index = recurse_mouse(
+            self.root,
+            &self.layouts,
+            &self.rects,
+            &self.children,
+            &mouse,
+        );
 
         if index == self.hovered {
             return;
@@ -131,7 +231,13 @@ impl DOM {
         }
 
         // Get index of currently hovered position
-        let index = recurse_mouse(self.root, &self.rects, &self.children, &self.mouse);
+        let index = recurse_mouse(
+            self.root,
+            &self.layouts,
+            &self.rects,
+            &self.children,
+            &self.mouse,
+        );
 
         // If clicked, reset hover state, then apply clicked state
         if clicked {
@@ -171,6 +277,38 @@ impl DOM {
         }
     }
 
+    /// Handle mouse wheel scrolling, by the given delta in pixels
+    /// (positive values scroll towards the end of the content).
+    ///
+    /// The childmost scroll container under the mouse that can scroll takes the delta.
+    /// Its descendants are repositioned and its subtree is marked dirty.
+    pub fn handle_mouse_wheel(&mut self, delta: [i32; 2]) {
+        let Some(index) = recurse_scroll(
+            self.root,
+            &self.layouts,
+            &self.rects,
+            &self.children,
+            &self.mouse,
+            &delta,
+        ) else {
+            return;
+        };
+
+        // Update the scroll offset, clamped to the content overflow
+        let rect = &mut self.rects[index];
+        for (dim, delta) in delta.into_iter().enumerate() {
+            if self.layouts[index].scroll[dim] {
+                rect.scroll[dim] = (rect.scroll[dim] + delta).clamp(0, rect.overflow(dim));
+            }
+        }
+
+        compute_positions(index, &self.layouts, &mut self.rects, &self.children);
+        self.dirty.push(index);
+
+        // The content moved under the mouse
+        self.handle_mouse_moved(self.mouse);
+    }
+
     /// Reset clicked and hover states
     pub fn reset_mouse(&mut self) {
         if let Some(clicked) = self.clicked {
@@ -190,22 +328,49 @@ impl DOM {
     ///
     /// Done by travesring the DOM tree, and adding recursively all indices of subtrees of dirty
     /// nodes. This ensures that parents are drawn before children, and that there are no
-    /// duplicates.
+    /// duplicates. Anchored children are drawn after in-flow children, to be layered over them.
     pub fn compute_redraw(&mut self) -> &[usize] {
         self.redraw.clear();
 
         // Recurse from the root node
-        recurse_explore_children(self.root, &self.children, &self.dirty, &mut self.redraw);
+        recurse_explore_children(
+            self.root,
+            &self.layouts,
+            &self.children,
+            &self.dirty,
+            &mut self.redraw,
+        );
 
         &self.redraw
     }
 }
 
+/// Children of a node in drawing order: in-flow children first, then anchored children
+fn drawing_order<'a>(
+    index: usize,
+    layouts: &'a [Layout],
+    children: &'a [Vec<usize>],
+) -> impl Iterator<Item = usize> + 'a {
+    let flow = children[index]
+        .iter()
+        .filter(|&&idx| layouts[idx].anchor.is_none());
+    let anchored = children[index]
+        .iter()
+        .filter(|&&idx| layouts[idx].anchor.is_some());
+
+    flow.chain(anchored).copied()
+}
+
 /// Recursively add children indices of the given index to the output vector
-fn recurse_add_children(index: usize, children: &[Vec<usize>], out: &mut Vec<usize>) {
+fn recurse_add_children(
+    index: usize,
+    layouts: &[Layout],
+    children: &[Vec<usize>],
+    out: &mut Vec<usize>,
+) {
     out.push(index);
-    for &child in &children[index] {
-        recurse_add_children(child, children, out);
+    for child in drawing_order(index, layouts, children) {
+        recurse_add_children(child, layouts, children, out);
     }
 }
 
@@ -213,16 +378,110 @@ fn recurse_add_children(index: usize, children: &[Vec<usize>], out: &mut Vec<usi
 /// Dirty subtrees are then explored using `recurse_add_children`.
 fn recurse_explore_children(
     index: usize,
+    layouts: &[Layout],
     children: &[Vec<usize>],
     dirty: &[usize],
     out: &mut Vec<usize>,
 ) {
     if dirty.contains(&index) {
-        recurse_add_children(index, children, out);
+        recurse_add_children(index, layouts, children, out);
         return;
     }
 
-    for &child in &children[index] {
-        recurse_explore_children(child, children, dirty, out);
+    for child in drawing_order(index, layouts, children) {
+        recurse_explore_children(child, layouts, children, dirty, out);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rice_layout::{Align, Anchor, Monospace, Size};
+
+    use super::*;
+
+    #[test]
+    fn test_relayout() {
+        let measure = Monospace::new(10, 20);
+
+        let mut dom = DOM::new();
+        let a = dom.insert(
+            Layout::new(Size::Fixed(100), Size::Fixed(50)),
+            StyleSheet::default(),
+        );
+        let b = dom.insert(
+            Layout::new(Size::Fixed(100), Size::Fixed(50)),
+            StyleSheet::default(),
+        );
+        let column = dom.insert_with_children(
+            Layout::new(Size::Fit, Size::Fit).vertical(Align::Start),
+            StyleSheet::default(),
+            vec![a, b],
+        );
+        dom.root = dom.insert_with_children(
+            Layout::new(Size::Fixed(400), Size::Fit).vertical(Align::Start),
+            StyleSheet::default(),
+            vec![column],
+        );
+        dom.compute_layout(dom.root, &measure);
+        assert_eq!(dom.rects[column].size, [100, 100]);
+
+        // Sizes resolved by the parent are recomputed
+        dom.set_layout(column, Layout::new(Size::Expand(1.0), Size::Fit));
+        dom.compute_relayout(&measure);
+        assert_eq!(dom.rects[column].size, [400, 100]);
+        assert_eq!(dom.dirty, vec![dom.root]);
+
+        // Size changes propagate to fit ancestors, and move the following siblings
+        dom.dirty.clear();
+        dom.set_layout(a, Layout::new(Size::Expand(1.0), Size::Fixed(80)));
+        dom.compute_relayout(&measure);
+        assert_eq!(dom.rects[a].size, [400, 80]);
+        assert_eq!(dom.rects[b].y(), 80);
+        assert_eq!(dom.rects[column].size, [400, 130]);
+        assert_eq!(dom.rects[dom.root].size, [400, 130]);
+        assert_eq!(dom.dirty, vec![dom.root]);
+
+        // Unchanged layouts are not recomputed
+        dom.dirty.clear();
+        dom.set_layout(a, Layout::new(Size::Expand(1.0), Size::Fixed(80)));
+        assert!(dom.relayout.is_empty());
+    }
+
+    #[test]
+    fn test_relayout_anchors() {
+        let measure = Monospace::new(10, 20);
+
+        let mut dom = DOM::new();
+        let a = dom.insert(
+            Layout::new(Size::Fixed(100), Size::Fixed(50)),
+            StyleSheet::default(),
+        );
+        let column = dom.insert_with_children(
+            Layout::new(Size::Fixed(200), Size::Fixed(200)).vertical(Align::Start),
+            StyleSheet::default(),
+            vec![a],
+        );
+        let popup = dom.insert(
+            Layout::new(Size::Fixed(50), Size::Fixed(20)).anchor(Anchor::node(
+                a,
+                [Align::Start, Align::End],
+                [Align::Start, Align::Start],
+            )),
+            StyleSheet::default(),
+        );
+        dom.root = dom.insert_with_children(
+            Layout::new(Size::Fixed(400), Size::Fit).vertical(Align::Start),
+            StyleSheet::default(),
+            vec![column, popup],
+        );
+        dom.compute_layout(dom.root, &measure);
+        assert_eq!(dom.rects[popup].position, [0, 50]);
+
+        // Nodes anchored to a resized node outside of the recomputed subtree follow it
+        dom.set_layout(a, Layout::new(Size::Fixed(100), Size::Fixed(80)));
+        dom.compute_relayout(&measure);
+        assert_eq!(dom.rects[a].size, [100, 80]);
+        assert_eq!(dom.rects[popup].position, [0, 80]);
+        assert_eq!(dom.dirty, vec![dom.root]);
     }
 }
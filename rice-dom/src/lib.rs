@@ -7,4 +7,7 @@ mod styles;
 
 pub use colors::Color;
 pub use dom::DOM;
-pub use styles::{ComputedStyle, StyleProp, StyleSheet, StyleValue};
+pub use styles::{
+    ColorStop, ComputedStyle, Gradient, GradientKind, ImageFit, Shadow, StyleProp, StyleSheet,
+    StyleValue,
+};
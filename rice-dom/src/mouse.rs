@@ -1,32 +1,79 @@
 //! Mouse handling
 
-use rice_layout::Rect;
+use rice_layout::{Layout, Rect};
 
 /// Recursively find the childmost rectangle containing the given point.
 /// The root rectangle (screen) is guaranteed to contain the point.
+///
+/// Anchored children are drawn over in-flow children and may overflow their parent,
+/// so they are checked first, even when the point is outside of their parent.
 pub fn recurse_mouse(
     index: usize,
+    layouts: &[Layout],
     rects: &[Rect],
     children: &[Vec<usize>],
     mouse: &[i32; 2],
 ) -> Option<usize> {
     // 1. Check if the point is inside this rect
     let rect = &rects[index];
-    if mouse[0] < rect.position[0]
-        || rect.size[0] + rect.position[0] < mouse[0]
-        || mouse[1] < rect.position[1]
-        || rect.size[1] + rect.position[1] < mouse[1]
-    {
-        // if not, stop
-        return None;
+    let inside = rect.position[0] <= mouse[0]
+        && mouse[0] <= rect.size[0] + rect.position[0]
+        && rect.position[1] <= mouse[1]
+        && mouse[1] <= rect.size[1] + rect.position[1];
+
+    // 2. Recurse through anchored children, then in-flow children if the point is inside
+    let anchored = children[index]
+        .iter()
+        .filter(|&&idx| layouts[idx].anchor.is_some());
+    let flow = children[index]
+        .iter()
+        .filter(|&&idx| inside && layouts[idx].anchor.is_none());
+
+    for idx in anchored.chain(flow) {
+        if let Some(idx) = recurse_mouse(*idx, layouts, rects, children, mouse) {
+            return Some(idx);
+        }
     }
 
-    // 2. Recurse through children
-    for idx in &children[index] {
-        if let Some(idx) = recurse_mouse(*idx, rects, children, mouse) {
+    // 3. If not inside, stop
+    inside.then_some(index)
+}
+
+/// Recursively find the childmost scroll container containing the given point,
+/// that can still scroll by the given delta along one of its axes.
+pub fn recurse_scroll(
+    index: usize,
+    layouts: &[Layout],
+    rects: &[Rect],
+    children: &[Vec<usize>],
+    mouse: &[i32; 2],
+    delta: &[i32; 2],
+) -> Option<usize> {
+    // 1. Stop if the point is outside of this rect
+    let rect = &rects[index];
+    let inside = rect.position[0] <= mouse[0]
+        && mouse[0] <= rect.size[0] + rect.position[0]
+        && rect.position[1] <= mouse[1]
+        && mouse[1] <= rect.size[1] + rect.position[1];
+
+    // 2. Recurse through children, the childmost container takes the scroll first
+    let anchored = children[index]
+        .iter()
+        .filter(|&&idx| layouts[idx].anchor.is_some());
+    let flow = children[index]
+        .iter()
+        .filter(|&&idx| inside && layouts[idx].anchor.is_none());
+
+    for idx in anchored.chain(flow) {
+        if let Some(idx) = recurse_scroll(*idx, layouts, rects, children, mouse, delta) {
             return Some(idx);
         }
     }
 
-    Some(index)
+    // 3. Check if this rect can scroll in the direction of the delta
+    let scrolls = (0..2).any(|dim| {
+        let offset = (rect.scroll[dim] + delta[dim]).clamp(0, rect.overflow(dim));
+        layouts[index].scroll[dim] && offset != rect.scroll[dim]
+    });
+    (inside && scrolls).then_some(index)
 }
@@ -16,12 +16,29 @@ pub struct StyleSheet {
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum StyleProp {
     BackgroundColor,
+    BackgroundGradient,
+    BorderRadius,
+    BorderWidth,
+    BorderColor,
+    BoxShadow,
+    Image,
+    ImageFit,
+    ImageTint,
 }
 
 /// Value for a style property
 #[derive(Debug, Clone, PartialEq)]
 pub enum StyleValue {
     Color(Color),
+    /// Logical pixel amounts for each corner: top left, top right, bottom right, bottom left
+    Corners([f32; 4]),
+    /// Logical pixel amounts for each side: top, right, bottom, left
+    Sides([f32; 4]),
+    Shadow(Shadow),
+    Gradient(Gradient),
+    /// Source of an image (e.g. the path of a PNG file)
+    Image(String),
+    Fit(ImageFit),
 }
 
 impl Default for StyleValue {
@@ -30,11 +47,78 @@ impl Default for StyleValue {
     }
 }
 
+/// Shadow drawn beneath an element
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Shadow {
+    /// Logical offset from the element
+    pub offset: [f32; 2],
+    /// Logical blur radius
+    pub blur: f32,
+    /// Logical amount the shadow grows by on each side, before blurring
+    pub spread: f32,
+    /// Color, or transparent for no shadow
+    pub color: Color,
+}
+
+/// Gradient between colors, drawn as the background of an element
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gradient {
+    pub kind: GradientKind,
+    /// Colors along the gradient, by increasing position
+    pub stops: Vec<ColorStop>,
+}
+
+/// Shape of a gradient
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GradientKind {
+    /// Gradient along a line through the center of the element, with an angle in degrees
+    /// clockwise from the top, as in CSS (e.g. 90 goes from left to right)
+    Linear { angle: f32 },
+    /// Gradient from a center, as fractions of the size of the element, to a logical radius
+    Radial { center: [f32; 2], radius: f32 },
+}
+
+/// Color at a position along a gradient
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColorStop {
+    pub color: Color,
+    /// Position from the start (0) to the end (1) of the gradient
+    pub position: f32,
+}
+
+/// How an image is scaled to the size of its element
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ImageFit {
+    /// Stretched to the element
+    #[default]
+    Fill,
+    /// Scaled to fit inside the element, keeping its aspect ratio
+    Contain,
+    /// Scaled to cover the element, keeping its aspect ratio and cropping the overflow
+    Cover,
+}
+
 /// Computed style ready for rendering
 #[derive(Debug, Clone, PartialEq, Default)]
 pub struct ComputedStyle {
     /// Background color
     pub background_color: Color,
+    /// Background gradient, drawn instead of the background color
+    pub background_gradient: Option<Gradient>,
+    /// Corner radii: top left, top right, bottom right, bottom left
+    pub border_radius: [f32; 4],
+    /// Border widths: top, right, bottom, left
+    pub border_width: [f32; 4],
+    /// Border color
+    pub border_color: Color,
+    /// Box shadow
+    pub shadow: Shadow,
+    /// Source of the image drawn over the background
+    pub image: Option<String>,
+    /// How the image is scaled to the element
+    pub image_fit: ImageFit,
+    /// Color multiplied with the image, if any
+    pub image_tint: Option<Color>,
 }
 
 impl StyleProp {
@@ -44,6 +128,30 @@ impl StyleProp {
             (StyleProp::BackgroundColor, StyleValue::Color(color)) => {
                 style.background_color = color.clone();
             }
+            (StyleProp::BackgroundGradient, StyleValue::Gradient(gradient)) => {
+                style.background_gradient = Some(gradient.clone());
+            }
+            (StyleProp::BorderRadius, StyleValue::Corners(radius)) => {
+                style.border_radius = *radius;
+            }
+            (StyleProp::BorderWidth, StyleValue::Sides(width)) => {
+                style.border_width = *width;
+            }
+            (StyleProp::BorderColor, StyleValue::Color(color)) => {
+                style.border_color = color.clone();
+            }
+            (StyleProp::BoxShadow, StyleValue::Shadow(shadow)) => {
+                style.shadow = shadow.clone();
+            }
+            (StyleProp::Image, StyleValue::Image(source)) => {
+                style.image = Some(source.clone());
+            }
+            (StyleProp::ImageFit, StyleValue::Fit(fit)) => {
+                style.image_fit = *fit;
+            }
+            (StyleProp::ImageTint, StyleValue::Color(color)) => {
+                style.image_tint = Some(color.clone());
+            }
             _ => unreachable!("Mismatched style property and value"),
         }
     }
@@ -54,6 +162,30 @@ impl StyleProp {
             StyleProp::BackgroundColor => {
                 style.background_color = Color::default();
             }
+            StyleProp::BackgroundGradient => {
+                style.background_gradient = None;
+            }
+            StyleProp::BorderRadius => {
+                style.border_radius = [0.0; 4];
+            }
+            StyleProp::BorderWidth => {
+                style.border_width = [0.0; 4];
+            }
+            StyleProp::BorderColor => {
+                style.border_color = Color::default();
+            }
+            StyleProp::BoxShadow => {
+                style.shadow = Shadow::default();
+            }
+            StyleProp::Image => {
+                style.image = None;
+            }
+            StyleProp::ImageFit => {
+                style.image_fit = ImageFit::default();
+            }
+            StyleProp::ImageTint => {
+                style.image_tint = None;
+            }
         }
     }
 }
@@ -0,0 +1,29 @@
+//! Formatter for Rice source files
+
+use anyhow::{Context, Result};
+
+mod components;
+mod enums;
+mod properties;
+mod root;
+mod utils;
+
+pub use root::format_source_file;
+
+/// Format a Rice source string
+pub fn format_string(input: &str) -> Result<String> {
+    let mut parser = tree_sitter::Parser::new();
+    parser
+        .set_language(&rice_grammar::LANGUAGE.into())
+        .context("Error loading Rice grammar")?;
+
+    let tree = parser
+        .parse(input, None)
+        .context("Error parsing content with Rice grammar")?;
+
+    let mut buffer = Vec::new();
+    format_source_file(tree, input.as_bytes(), &mut buffer)?;
+    let formatted = String::from_utf8(buffer).expect("Invalid UTF-8");
+
+    Ok(formatted)
+}
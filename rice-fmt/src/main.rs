@@ -4,13 +4,7 @@ use anyhow::{Context, Result};
 use clap::Parser;
 use walkdir::WalkDir;
 
-use crate::root::format_source_file;
-
-mod components;
-mod enums;
-mod properties;
-mod root;
-mod utils;
+use rice_fmt::{format_source_file, format_string};
 
 /// Format Rice files
 #[derive(clap::Parser, Debug)]
@@ -127,20 +121,3 @@ pub fn format_file<P: AsRef<Path>>(path: P, inplace: bool, check: bool) -> Resul
 
     Ok(true)
 }
-
-pub fn format_string(input: &str) -> Result<String> {
-    let mut parser = tree_sitter::Parser::new();
-    parser
-        .set_language(&rice_grammar::LANGUAGE.into())
-        .context("Error loading Rice grammar")?;
-
-    let tree = parser
-        .parse(input, None)
-        .context("Error parsing content with Rice grammar")?;
-
-    let mut buffer = Vec::new();
-    format_source_file(tree, input.as_bytes(), &mut buffer)?;
-    let formatted = String::from_utf8(buffer).expect("Invalid UTF-8");
-
-    Ok(formatted)
-}
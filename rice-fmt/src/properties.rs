@@ -60,6 +60,15 @@ pub fn format_property<W: io::Write>(
                 writer.write_all(b": ")?;
                 writer.write_all(&content[child.byte_range()])?;
             }
+            // Multiple values, separated by a single space
+            "values" => {
+                writer.write_all(b":")?;
+                for value in child.named_children(&mut child.walk()) {
+                    node_error(value, content)?;
+                    writer.write_all(b" ")?;
+                    writer.write_all(&content[value.byte_range()])?;
+                }
+            }
             _ => unreachable!("Unexpected node kind for property: {}", child.kind()),
         };
     }
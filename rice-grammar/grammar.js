@@ -92,7 +92,12 @@ export default grammar({
 
     property: ($) =>
       choice(
-        seq($.propname, optional(seq(".", $.identifier)), ":", $.value),
+        seq(
+          $.propname,
+          repeat(seq(".", $.identifier)),
+          ":",
+          choice($.value, $.values),
+        ),
         $.propname,
       ),
 
@@ -108,17 +113,36 @@ export default grammar({
         $.pixels,
         $.fraction,
         $.percentage,
+        $.angle,
         $.identifier,
         $.hex_color,
       ),
 
+    // Multiple space-separated amounts & colors, for shorthands
+    // (e.g. "padding: 4px 8px", "shadow: 0px 2px 8px #00000080").
+    // Only the first one can be an identifier (e.g. "bg_gradient: linear 90deg #fff #000"),
+    // so that the next property name is not taken for a value. Properties of declared
+    // components can thus only be used as single values (e.g. "padding: pad", but not
+    // "padding: pad 4px", nor "padding: 4px pad" which is parsed as "padding: 4px" then "pad").
+    values: ($) =>
+      seq(alias($._head, $.value), repeat1(alias($._item, $.value))),
+
+    _head: ($) => choice($._item, $.identifier),
+
+    _item: ($) =>
+      choice($.pixels, $.fraction, $.percentage, $.angle, $.hex_color),
+
     boolean: (_) => choice("true", "false"),
 
     // Anything between double quotes. Escaped quotes are allowed.
     string: (_) => /"(?:[^"\\]|\\.)*"/,
 
-    // Pixel amounts (e.g. "10px", "20px")
-    pixels: (_) => /[0-9]+px/,
+    // Pixel amounts (e.g. "10px", "-2px"). Negative amounts are only accepted by the parser
+    // for offsets (e.g. of shadows), and reported elsewhere
+    pixels: (_) => /-?[0-9]+px/,
+
+    // Angles in degrees (e.g. 90deg, -22.5deg)
+    angle: (_) => /-?[0-9]+(?:\.[0-9]+)?deg/,
 
     // Fraction amounts (e.g. 1fr, 2.5fr)
     fraction: (_) => /[0-9]+(?:\.[0-9]+)?fr/,
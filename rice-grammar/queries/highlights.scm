@@ -40,4 +40,5 @@
 (pixels) @number
 (fraction) @number
 (percentage) @number
+(angle) @number
 
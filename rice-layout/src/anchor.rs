@@ -0,0 +1,47 @@
+//! Out-of-flow positioning
+
+use crate::Align;
+
+/// Anchor for a node positioned out of its parent's layout flow.
+///
+/// The `origin` point of the node's box is placed on the `at` point of the target's box
+/// (along each axis), then moved by the offset.
+/// For instance, a dropdown below a button is anchored at `[Start, End]` of the button
+/// with a `[Start, Start]` origin.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Anchor {
+    /// Node to anchor to, or the parent node if absent
+    pub target: Option<usize>,
+    /// Point on the target's box, along x and y
+    pub at: [Align; 2],
+    /// Point on the node's own box, along x and y
+    pub origin: [Align; 2],
+    /// Pixel offset from the target point, along x and y
+    pub offset: [i32; 2],
+}
+
+impl Anchor {
+    /// Anchor to the parent node
+    pub fn parent(at: [Align; 2], origin: [Align; 2]) -> Self {
+        Self {
+            at,
+            origin,
+            ..Default::default()
+        }
+    }
+
+    /// Anchor to another node
+    pub fn node(target: usize, at: [Align; 2], origin: [Align; 2]) -> Self {
+        Self {
+            target: Some(target),
+            at,
+            origin,
+            ..Default::default()
+        }
+    }
+
+    pub fn offset(mut self, x: i32, y: i32) -> Self {
+        self.offset = [x, y];
+        self
+    }
+}
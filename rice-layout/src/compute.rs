@@ -1,26 +1,93 @@
 //! Layout engine using arena for storage
 
-use crate::{Direction, Layout, Rect};
+use crate::{Anchor, Direction, Layout, Measure, Rect};
 use crate::{Size, utils::*};
 
-/// Compute a layout starting from the given node index
+/// Compute a layout starting from the given node index.
+/// Text components are measured with the given glyph metrics provider.
 pub fn compute_layout(
     root: usize,
     layouts: &[Layout],
     rects: &mut [Rect],
     children: &[Vec<usize>],
+    measure: &dyn Measure,
 ) {
+    // Children that take part in their parent's layout flow (not anchored)
+    let flow = &flow_children(root, layouts, children);
+
     // 1st pass: compute fixed widths (top-down)
     recurse_fixed(root, layouts, rects, children, 0);
     // 2nd pass: compute expand widths (bottom-up)
-    recurse_grow_width(root, layouts, rects, children);
-    // 3rd pass: compute text height after wrapping (todo)
+    recurse_grow_width(root, layouts, rects, children, flow, measure);
+    // 3rd pass: compute heights derived from widths, for text & aspect ratios (top-down)
+    recurse_derived(root, layouts, rects, children, measure);
     // 4th pass: compute fixed heights (top-down)
     recurse_fixed(root, layouts, rects, children, 1);
     // 5th pass: compute expand heights (bottom-up)
-    recurse_grow_height(root, layouts, rects, children);
+    recurse_grow_height(root, layouts, rects, children, flow);
     // 6th pass: compute positions (top-down)
-    recurse_positions(root, layouts, rects, children);
+    position_tree(root, layouts, rects, children, flow);
+}
+
+/// Compute the positions of the descendants of the given node, whose sizes are already known.
+/// Used on its own when only scroll offsets changed.
+pub fn compute_positions(
+    root: usize,
+    layouts: &[Layout],
+    rects: &mut [Rect],
+    children: &[Vec<usize>],
+) {
+    let flow = &flow_children(root, layouts, children);
+    position_tree(root, layouts, rects, children, flow);
+}
+
+/// Compute positions from the given root node, in a top-down way
+fn position_tree(
+    root: usize,
+    layouts: &[Layout],
+    rects: &mut [Rect],
+    children: &[Vec<usize>],
+    flow: &[Vec<usize>],
+) {
+    // Nodes anchored to another node are positioned last, once their target is positioned
+    let mut deferred = Vec::new();
+    recurse_positions(root, layouts, rects, children, flow, &mut deferred);
+
+    // Nodes within deferred subtrees, whose positions are not known yet
+    let mut pending = vec![false; rects.len()];
+    for &(_, node) in &deferred {
+        mark_subtree(node, children, &mut pending, true);
+    }
+
+    while !deferred.is_empty() {
+        // Position a node whose target is already positioned (or the first one, on cycles)
+        let ready = deferred
+            .iter()
+            .position(|&(_, node)| {
+                let target = layouts[node].anchor.and_then(|anchor| anchor.target);
+                target.is_none_or(|target| !pending[target])
+            })
+            .unwrap_or(0);
+        let (parent, node) = deferred.remove(ready);
+        mark_subtree(node, children, &mut pending, false);
+
+        if let Some(anchor) = layouts[node].anchor {
+            position_anchored(node, parent, rects, layouts, anchor);
+        }
+        let start = deferred.len();
+        recurse_positions(node, layouts, rects, children, flow, &mut deferred);
+        for &(_, idx) in &deferred[start..] {
+            mark_subtree(idx, children, &mut pending, true);
+        }
+    }
+}
+
+/// Set the flag of a node and all of its descendants
+fn mark_subtree(node: usize, children: &[Vec<usize>], flags: &mut [bool], value: bool) {
+    flags[node] = value;
+    for &idx in &children[node] {
+        mark_subtree(idx, children, flags, value);
+    }
 }
 
 /// Compute fixed & percent dimensions in a top-down recursive way
@@ -46,24 +113,59 @@ fn recurse_grow_width(
     layouts: &[Layout],
     rects: &mut [Rect],
     children: &[Vec<usize>],
+    flow: &[Vec<usize>],
+    measure: &dyn Measure,
 ) {
     // 1. Recurse children
     for &idx in &children[node] {
-        recurse_grow_width(idx, layouts, rects, children);
+        recurse_grow_width(idx, layouts, rects, children, flow, measure);
     }
 
-    // 2. If this node is "fit", compute its size based on children + direction
+    // 2. If this node is "fit", compute its size based on text or children + direction
     if let Size::Fit = layouts[node].size[0] {
-        match layouts[node].direction {
-            Direction::Horizontal(_) => fit_along(node, rects, layouts, children, 0),
-            Direction::Vertical(_) => fit_across(node, rects, layouts, children, 0),
+        match (&layouts[node].text, layouts[node].direction) {
+            (Some(text), _) => fit_text_width(node, rects, layouts, text, measure),
+            (None, Direction::Horizontal(_)) => fit_along(node, rects, layouts, flow, 0),
+            (None, Direction::Vertical(_)) => fit_across(node, rects, layouts, flow, 0),
+            (None, Direction::Grid(_)) => fit_grid(node, rects, layouts, flow, 0),
         }
     }
 
     // 3. Expand all children of this node that are expandable
     match layouts[node].direction {
-        Direction::Horizontal(_) => expand_along(node, rects, layouts, children, 0),
-        Direction::Vertical(_) => expand_across(node, rects, layouts, children, 0),
+        Direction::Horizontal(_) if layouts[node].wraps() => {
+            expand_lines_along(node, rects, layouts, flow)
+        }
+        Direction::Horizontal(_) => expand_along(node, rects, layouts, flow, 0),
+        Direction::Vertical(_) => expand_across(node, rects, layouts, flow, 0),
+        Direction::Grid(_) => expand_grid(node, rects, layouts, flow, 0),
+    }
+    expand_anchored(node, rects, layouts, children, 0);
+}
+
+/// Compute the height of text components wrapped to their width, and of components with an
+/// aspect ratio, in a top-down recursive way
+fn recurse_derived(
+    node: usize,
+    layouts: &[Layout],
+    rects: &mut [Rect],
+    children: &[Vec<usize>],
+    measure: &dyn Measure,
+) {
+    // 1. Compute the height from the aspect ratio, or from the wrapped lines of a "fit" text
+    match (
+        layouts[node].aspect_ratio,
+        &layouts[node].text,
+        layouts[node].size[1],
+    ) {
+        (Some(ratio), _, _) => ratio_height(node, rects, layouts, ratio),
+        (None, Some(text), Size::Fit) => fit_text_height(node, rects, layouts, text, measure),
+        _ => {}
+    }
+
+    // 2. Recurse children
+    for &idx in &children[node] {
+        recurse_derived(idx, layouts, rects, children, measure);
     }
 }
 
@@ -73,33 +175,68 @@ fn recurse_grow_height(
     layouts: &[Layout],
     rects: &mut [Rect],
     children: &[Vec<usize>],
+    flow: &[Vec<usize>],
 ) {
     // 1. Recurse children
     for &idx in &children[node] {
-        recurse_grow_height(idx, layouts, rects, children);
+        recurse_grow_height(idx, layouts, rects, children, flow);
     }
 
-    // 2. If this node is "fit", compute its size based on children + direction
-    if let Size::Fit = layouts[node].size[1] {
+    // 2. If this node is "fit", compute its size based on children + direction.
+    // Text heights and heights derived from aspect ratios were already computed.
+    if let (None, Size::Fit) = (&layouts[node].text, layouts[node].size[1])
+        && !layouts[node].derived(1)
+    {
         match layouts[node].direction {
-            Direction::Horizontal(_) => fit_across(node, rects, layouts, children, 1),
-            Direction::Vertical(_) => fit_along(node, rects, layouts, children, 1),
+            Direction::Horizontal(_) if layouts[node].wraps() => {
+                fit_lines(node, rects, layouts, flow)
+            }
+            Direction::Horizontal(_) => fit_across(node, rects, layouts, flow, 1),
+            Direction::Vertical(_) => fit_along(node, rects, layouts, flow, 1),
+            Direction::Grid(_) => fit_grid(node, rects, layouts, flow, 1),
         }
     }
 
     // 3. Expand all children of this node that are expandable
     match layouts[node].direction {
-        Direction::Horizontal(_) => expand_across(node, rects, layouts, children, 1),
-        Direction::Vertical(_) => expand_along(node, rects, layouts, children, 1),
+        Direction::Horizontal(_) if layouts[node].wraps() => {
+            expand_lines_across(node, rects, layouts, flow)
+        }
+        Direction::Horizontal(_) => expand_across(node, rects, layouts, flow, 1),
+        Direction::Vertical(_) => expand_along(node, rects, layouts, flow, 1),
+        Direction::Grid(_) => expand_grid(node, rects, layouts, flow, 1),
     }
+    expand_anchored(node, rects, layouts, children, 1);
 }
 
-fn recurse_positions(node: usize, layouts: &[Layout], rects: &mut [Rect], children: &[Vec<usize>]) {
-    // 1. Compute positions of childre
-    positions(node, rects, layouts, children);
+/// Compute positions in a top-down recursive way.
+/// Children anchored to other nodes are deferred along with their parent index.
+fn recurse_positions(
+    node: usize,
+    layouts: &[Layout],
+    rects: &mut [Rect],
+    children: &[Vec<usize>],
+    flow: &[Vec<usize>],
+    deferred: &mut Vec<(usize, usize)>,
+) {
+    // 1. Compute positions of in-flow children
+    positions(node, rects, layouts, flow);
+
+    // 2. Measure the content extent, and move in-flow children by the scroll offset
+    scroll_content(node, rects, layouts, flow);
 
-    // 2. Recurse children
+    // 3. Recurse children, positioning children anchored to this node
     for &idx in &children[node] {
-        recurse_positions(idx, layouts, rects, children);
+        match layouts[idx].anchor {
+            Some(anchor @ Anchor { target: None, .. }) => {
+                position_anchored(idx, node, rects, layouts, anchor);
+            }
+            Some(_) => {
+                deferred.push((node, idx));
+                continue;
+            }
+            None => {}
+        }
+        recurse_positions(idx, layouts, rects, children, flow, deferred);
     }
 }
@@ -1,6 +1,6 @@
 //! Layout directions
 
-/// Alignment across the layout direction
+/// Alignment across the layout direction, or within grid cells
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum Align {
     #[default]
@@ -9,11 +9,36 @@ pub enum Align {
     End,
 }
 
+/// Alignment of a child across its parent layout direction, overriding the parent alignment
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlignSelf {
+    Start,
+    Center,
+    End,
+    /// Fill the parent across its layout direction like `Size::Expand` (for fit sizes)
+    Stretch,
+}
+
+/// Justification of children along the layout direction
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Justify {
+    #[default]
+    Start,
+    Center,
+    End,
+    /// Equal space around each child (half-size space at both ends)
+    SpaceAround,
+    /// Equal space between children and at both ends
+    SpaceEvenly,
+}
+
 /// Layout directions for content
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Direction {
     Vertical(Align),
     Horizontal(Align),
+    /// Children placed in grid cells, aligned on both axes within their cells
+    Grid(Align),
 }
 
 impl Default for Direction {
@@ -27,6 +52,7 @@ impl Direction {
         match self {
             Direction::Horizontal(align) => *align,
             Direction::Vertical(align) => *align,
+            Direction::Grid(align) => *align,
         }
     }
 }
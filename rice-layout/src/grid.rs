@@ -0,0 +1,59 @@
+//! Grid tracks & cell placement
+
+use crate::{Gap, Size};
+
+/// Track definitions for a grid layout
+/// - `Fixed`: fixed pixel track
+/// - `Expand`: fraction of the remaining space (fr track)
+/// - `Percent`: percentage of the parent inner size
+/// - `Fit`: fit the largest child in the track
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Grid {
+    /// Column tracks (a single fit column if empty)
+    pub columns: Vec<Size>,
+    /// Row tracks (rows needed beyond these are added as fit tracks)
+    pub rows: Vec<Size>,
+    /// Gap between columns, and between rows
+    pub gap: [Gap; 2],
+}
+
+impl Grid {
+    pub fn new(columns: Vec<Size>, rows: Vec<Size>) -> Self {
+        Self {
+            columns,
+            rows,
+            ..Default::default()
+        }
+    }
+
+    pub fn gap(mut self, columns: Gap, rows: Gap) -> Self {
+        self.gap = [columns, rows];
+        self
+    }
+
+    /// Track definitions along the given dimension
+    pub fn tracks(&self, dim: usize) -> &[Size] {
+        match dim {
+            0 => &self.columns,
+            _ => &self.rows,
+        }
+    }
+}
+
+/// Placement of a child within its parent grid
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cell {
+    /// Column & row of the cell, or automatic placement in the next free cell
+    pub position: Option<[usize; 2]>,
+    /// Amount of columns & rows spanned
+    pub span: [usize; 2],
+}
+
+impl Default for Cell {
+    fn default() -> Self {
+        Self {
+            position: None,
+            span: [1, 1],
+        }
+    }
+}
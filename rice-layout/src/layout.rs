@@ -1,5 +1,5 @@
 /// Layout constraints
-use crate::{Align, Direction, Gap, Insets, Size};
+use crate::{Align, AlignSelf, Anchor, Cell, Direction, Gap, Grid, Insets, Justify, Size};
 
 /// Layout rules
 #[derive(Debug, Clone, PartialEq, Default)]
@@ -7,13 +7,32 @@ pub struct Layout {
     pub size: [Size; 2],
     pub min_size: [Option<i32>; 2],
     pub max_size: [Option<i32>; 2],
+    /// Width / height ratio: the height is derived from the computed width
+    pub aspect_ratio: Option<f32>,
 
     // Margin & padding expressed along x, y axes directions
     pub margin: Insets,
     pub padding: Insets,
 
     pub direction: Direction,
+    pub justify: Justify,
     pub gap: Gap,
+    /// Wrap children into lines with the given gap between lines (horizontal directions only)
+    pub wrap: Option<Gap>,
+    /// Scrollable axes: overflowing content is moved by the scroll offset
+    pub scroll: [bool; 2],
+
+    // Alignment override across the parent layout direction
+    pub align_self: Option<AlignSelf>,
+    // Out-of-flow positioning, skipped by the parent layout
+    pub anchor: Option<Anchor>,
+
+    // Grid tracks (for grid directions) & cell placement within a parent grid
+    pub grid: Grid,
+    pub cell: Cell,
+
+    /// Text content, wrapped to the computed width
+    pub text: Option<String>,
 }
 
 impl Layout {
@@ -24,6 +43,11 @@ impl Layout {
         }
     }
 
+    pub fn aspect_ratio(mut self, ratio: f32) -> Self {
+        self.aspect_ratio = Some(ratio);
+        self
+    }
+
     pub fn margin(mut self, insets: Insets) -> Self {
         self.margin = insets;
         self
@@ -44,11 +68,57 @@ impl Layout {
         self
     }
 
+    pub fn align_self(mut self, align: AlignSelf) -> Self {
+        self.align_self = Some(align);
+        self
+    }
+
+    pub fn anchor(mut self, anchor: Anchor) -> Self {
+        self.anchor = Some(anchor);
+        self
+    }
+
+    pub fn justify(mut self, justify: Justify) -> Self {
+        self.justify = justify;
+        self
+    }
+
+    pub fn wrap(mut self, line_gap: Gap) -> Self {
+        self.wrap = Some(line_gap);
+        self
+    }
+
+    pub fn scroll(mut self, x: bool, y: bool) -> Self {
+        self.scroll = [x, y];
+        self
+    }
+
+    pub fn grid(mut self, grid: Grid, align: Align) -> Self {
+        self.direction = Direction::Grid(align);
+        self.grid = grid;
+        self
+    }
+
+    pub fn cell(mut self, column: usize, row: usize) -> Self {
+        self.cell.position = Some([column, row]);
+        self
+    }
+
+    pub fn span(mut self, columns: usize, rows: usize) -> Self {
+        self.cell.span = [columns, rows];
+        self
+    }
+
     pub fn gap(mut self, gap: Gap) -> Self {
         self.gap = gap;
         self
     }
 
+    pub fn text(mut self, text: &str) -> Self {
+        self.text = Some(text.to_string());
+        self
+    }
+
     pub fn max_width(mut self, max: i32) -> Self {
         self.max_size[0] = Some(max);
         self
@@ -69,6 +139,23 @@ impl Layout {
         self
     }
 
+    /// Whether children wrap into lines.
+    /// Only horizontal layouts can wrap, as lines are computed from the resolved width.
+    pub fn wraps(&self) -> bool {
+        matches!(self.direction, Direction::Horizontal(_)) && self.wrap.is_some()
+    }
+
+    /// Whether the size along a dimension is derived from the aspect ratio (heights only).
+    /// Derived sizes take precedence over the size rules of the layout.
+    pub fn derived(&self, dim: usize) -> bool {
+        dim == 1 && self.aspect_ratio.is_some()
+    }
+
+    /// Whether the component expands to the available space along a dimension
+    pub fn expands(&self, dim: usize) -> bool {
+        matches!(self.size[dim], Size::Expand(_)) && !self.derived(dim)
+    }
+
     pub fn clip_size(&self, dim: usize, size: i32) -> i32 {
         let mut clipped = size;
         if let Some(min) = self.min_size[dim] {
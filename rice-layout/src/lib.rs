@@ -1,21 +1,27 @@
 //! 2D layout engine
 
+mod anchor;
 mod compute;
 mod direction;
 mod gap;
+mod grid;
 mod insets;
 mod layout;
 mod rect;
 mod size;
+mod text;
 mod utils;
 
-pub use compute::compute_layout;
-pub use direction::{Align, Direction};
+pub use anchor::Anchor;
+pub use compute::{compute_layout, compute_positions};
+pub use direction::{Align, AlignSelf, Direction, Justify};
 pub use gap::Gap;
+pub use grid::{Cell, Grid};
 pub use insets::Insets;
 pub use layout::Layout;
 pub use rect::Rect;
 pub use size::Size;
+pub use text::{Measure, Monospace, text_height, text_width, wrap_text};
 
 #[cfg(test)]
 mod tests {
@@ -29,6 +35,8 @@ mod tests {
         pub rects: Vec<Rect>,
         /// Children indices for each node
         pub children: Vec<Vec<usize>>,
+        /// Glyph metrics for text components
+        pub measure: Monospace,
     }
 
     impl Arena {
@@ -38,12 +46,19 @@ mod tests {
                 layouts: Vec::new(),
                 rects: Vec::new(),
                 children: Vec::new(),
+                measure: Monospace::new(10, 20),
             }
         }
 
         /// Recompute the entire layout starting from the given root node
         pub fn compute_layout(&mut self, root: usize) {
-            compute_layout(root, &self.layouts, &mut self.rects, &self.children);
+            compute_layout(
+                root,
+                &self.layouts,
+                &mut self.rects,
+                &self.children,
+                &self.measure,
+            );
         }
 
         /// Insert a root node into the arena
@@ -679,4 +694,586 @@ mod tests {
         assert_eq!(child1.height(), 500); // Should respect min_height
         assert_eq!(child2.height(), 500); // Should take remaining space
     }
+
+    #[test]
+    fn test_wrap_text() {
+        let measure = Monospace::new(10, 20);
+        let text = "lorem ipsum  dolor\nsit amet";
+
+        // Wide enough: only explicit line breaks
+        let lines = wrap_text(text, 1_000, &measure);
+        assert_eq!(lines.len(), 2);
+        assert_eq!(&text[lines[0].clone()], "lorem ipsum  dolor");
+        assert_eq!(&text[lines[1].clone()], "sit amet");
+
+        // Break between words, collapsing whitespaces at line ends
+        let lines = wrap_text(text, 120, &measure);
+        let lines: Vec<_> = lines.into_iter().map(|range| &text[range]).collect();
+        assert_eq!(lines, vec!["lorem ipsum", "dolor", "sit amet"]);
+
+        // Words wider than the available width overflow on their own line
+        let lines = wrap_text(text, 10, &measure);
+        assert_eq!(lines.len(), 5);
+
+        assert_eq!(text_width(text, &measure), 180);
+        assert_eq!(text_height("", 100, &measure), 20);
+    }
+
+    #[test]
+    fn test_text_layout() {
+        // Fit text takes its unwrapped size
+        let mut arena = Arena::new();
+        let root = arena.insert(Layout::default());
+        let text = arena.insert_child(Layout::default().text("hello world"), root);
+        arena.compute_layout(root);
+        assert_eq!(arena.rects[text].width(), 110);
+        assert_eq!(arena.rects[text].height(), 20);
+
+        // Text wraps to a fixed width, and fit parents take the wrapped height
+        for &padding in SIZES {
+            let mut arena = Arena::new();
+            let root = arena.insert(Layout::default().padding(Insets::uniform(padding)));
+            let text = arena.insert_child(
+                Layout::new(Size::Fixed(60), Size::Fit)
+                    .padding(Insets::uniform(padding))
+                    .text("hello world"),
+                root,
+            );
+            arena.compute_layout(root);
+
+            // "hello world" (110px) never fits within 60px: one line per word
+            assert_eq!(arena.rects[text].height(), 2 * 20 + 2 * padding);
+            assert_eq!(arena.rects[root].height(), 2 * 20 + 4 * padding);
+        }
+
+        // Text wraps to an expanded width, and respects max width
+        let mut arena = Arena::new();
+        let root = arena.insert(Layout::new(Size::Fixed(1_000), Size::Fit));
+        let expand = arena.insert_child(
+            Layout::new(Size::Expand(1.0), Size::Fit)
+                .max_width(50)
+                .text("a bb ccc"),
+            root,
+        );
+        let fit = arena.insert_child(Layout::default().max_width(30).text("a bb ccc"), root);
+        arena.compute_layout(root);
+
+        assert_eq!(arena.rects[expand].width(), 50);
+        assert_eq!(arena.rects[expand].height(), 40);
+        assert_eq!(arena.rects[fit].width(), 30);
+        assert_eq!(arena.rects[fit].height(), 60);
+        assert_eq!(arena.rects[root].height(), 100);
+    }
+
+    #[test]
+    fn test_grid_fit() {
+        for &padding in SIZES {
+            for &gap in SIZES {
+                let mut arena = Arena::new();
+                let root = arena.insert(
+                    Layout::default().padding(Insets::uniform(padding)).grid(
+                        Grid::new(vec![Size::Fixed(100), Size::Fit], vec![])
+                            .gap(Gap::Fixed(gap), Gap::Fixed(gap)),
+                        Align::Start,
+                    ),
+                );
+                let cells: Vec<_> = [(50, 50), (80, 30), (20, 60), (60, 10), (10, 10)]
+                    .into_iter()
+                    .map(|(w, h)| {
+                        arena.insert_child(Layout::new(Size::Fixed(w), Size::Fixed(h)), root)
+                    })
+                    .collect();
+                arena.compute_layout(root);
+
+                // 2 columns (100px, fit = 80px), 3 fit rows (50px, 60px, 10px)
+                let root = &arena.rects[root];
+                assert_eq!(root.width(), 100 + 80 + gap + 2 * padding);
+                assert_eq!(root.height(), 50 + 60 + 10 + 2 * gap + 2 * padding);
+
+                let positions: Vec<_> = cells
+                    .iter()
+                    .map(|&idx| (arena.rects[idx].x(), arena.rects[idx].y()))
+                    .collect();
+                let column = padding + 100 + gap;
+                let row = padding + 50 + gap;
+                assert_eq!(
+                    positions,
+                    vec![
+                        (padding, padding),
+                        (column, padding),
+                        (padding, row),
+                        (column, row),
+                        (padding, row + 60 + gap),
+                    ]
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_grid_expand() {
+        for &gap in SIZES {
+            for &fr in FR {
+                let mut arena = Arena::new();
+                let root = arena.insert(
+                    Layout::new(Size::Fixed(1_600), Size::Fixed(1_000)).grid(
+                        Grid::new(
+                            vec![Size::Expand(1.0), Size::Expand(fr), Size::Percent(0.25)],
+                            vec![Size::Fixed(100), Size::Expand(1.0)],
+                        )
+                        .gap(Gap::Fixed(gap), Gap::Auto),
+                        Align::Center,
+                    ),
+                );
+                // Spans the 2 expand columns, and fills them
+                let wide = arena.insert_child(
+                    Layout::new(Size::Expand(1.0), Size::Expand(1.0)).span(2, 1),
+                    root,
+                );
+                // Centered within the percent column
+                let small =
+                    arena.insert_child(Layout::new(Size::Fixed(100), Size::Fixed(50)), root);
+                // Fills the expand row in the first column
+                let tall = arena.insert_child(
+                    Layout::new(Size::Fixed(10), Size::Expand(1.0)).cell(0, 1),
+                    root,
+                );
+                arena.compute_layout(root);
+
+                let remaining = (1_600 - 400 - 2 * gap) as f32;
+                let col0 = (remaining / (1.0 + fr)).round() as i32;
+                let col1 = (remaining * fr / (1.0 + fr)).round() as i32;
+
+                let wide = &arena.rects[wide];
+                assert_eq!(wide.width(), col0 + col1 + gap);
+                assert_eq!(wide.height(), 100);
+                assert_eq!((wide.x(), wide.y()), (0, 0));
+
+                let small = &arena.rects[small];
+                assert_eq!(small.x(), col0 + col1 + 2 * gap + 150);
+                assert_eq!(small.y(), 25);
+
+                let tall = &arena.rects[tall];
+                assert_eq!(tall.height(), 900);
+                assert_eq!(tall.x(), (col0 - 10) / 2);
+                assert_eq!(tall.y(), 100);
+            }
+        }
+    }
+
+    #[test]
+    fn test_grid_placement() {
+        let mut arena = Arena::new();
+        let root = arena.insert(Layout::default().grid(
+            Grid::new(vec![Size::Fixed(10); 3], vec![Size::Fixed(10); 3]),
+            Align::Start,
+        ));
+        let auto1 = arena.insert_child(Layout::default().span(2, 1), root);
+        let auto2 = arena.insert_child(Layout::default().span(2, 1), root);
+        let explicit = arena.insert_child(Layout::default().cell(0, 1).span(1, 2), root);
+        let auto3 = arena.insert_child(Layout::default(), root);
+        let overflow = arena.insert_child(Layout::default().span(5, 1), root);
+
+        let cells = utils::grid_cells(root, &arena.layouts, &arena.children);
+        let position =
+            |idx: usize| cells[arena.children[root].iter().position(|&i| i == idx).unwrap()];
+
+        assert_eq!(position(auto1), ([0, 0], [2, 1]));
+        assert_eq!(position(auto2), ([1, 1], [2, 1]));
+        assert_eq!(position(explicit), ([0, 1], [1, 2]));
+        assert_eq!(position(auto3), ([1, 2], [1, 1]));
+        assert_eq!(position(overflow), ([0, 3], [3, 1]));
+
+        // Implicit rows are fit tracks
+        arena.compute_layout(root);
+        assert_eq!(arena.rects[root].height(), 30);
+    }
+
+    #[test]
+    fn test_justify() {
+        for &padding in SIZES {
+            for &gap in SIZES {
+                for justify in [
+                    Justify::Start,
+                    Justify::Center,
+                    Justify::End,
+                    Justify::SpaceAround,
+                    Justify::SpaceEvenly,
+                ] {
+                    let mut arena = Arena::new();
+                    let root = arena.insert(
+                        Layout::new(Size::Fixed(1_600), Size::Fit)
+                            .padding(Insets::uniform(padding))
+                            .gap(Gap::Fixed(gap))
+                            .horizontal(Align::Start)
+                            .justify(justify),
+                    );
+                    let child1 =
+                        arena.insert_child(Layout::new(Size::Fixed(100), Size::Fixed(100)), root);
+                    let child2 =
+                        arena.insert_child(Layout::new(Size::Fixed(200), Size::Fixed(100)), root);
+                    arena.compute_layout(root);
+
+                    let free = 1_600 - 2 * padding - 300 - gap;
+                    let (x1, x2) = match justify {
+                        Justify::Start => (padding, padding + 100 + gap),
+                        Justify::Center => (padding + free / 2, padding + free / 2 + 100 + gap),
+                        Justify::End => (padding + free, padding + free + 100 + gap),
+                        Justify::SpaceAround => {
+                            let spacing = free / 2;
+                            (
+                                padding + spacing / 2,
+                                padding + spacing / 2 + 100 + gap + spacing,
+                            )
+                        }
+                        Justify::SpaceEvenly => {
+                            let spacing = free / 3;
+                            (padding + spacing, padding + 2 * spacing + 100 + gap)
+                        }
+                    };
+
+                    assert_eq!(arena.rects[child1].x(), x1);
+                    assert_eq!(arena.rects[child2].x(), x2);
+                }
+            }
+        }
+
+        // No effect with auto gaps
+        let mut arena = Arena::new();
+        let root = arena.insert(
+            Layout::new(Size::Fit, Size::Fixed(1_000))
+                .gap(Gap::Auto)
+                .justify(Justify::Center),
+        );
+        let child1 = arena.insert_child(Layout::new(Size::Fixed(100), Size::Fixed(100)), root);
+        let child2 = arena.insert_child(Layout::new(Size::Fixed(100), Size::Fixed(100)), root);
+        arena.compute_layout(root);
+
+        assert_eq!(arena.rects[child1].y(), 0);
+        assert_eq!(arena.rects[child2].y(), 900);
+    }
+
+    #[test]
+    fn test_align_self() {
+        for &padding in SIZES {
+            for &margin in SIZES {
+                let mut arena = Arena::new();
+                let root = arena.insert(
+                    Layout::new(Size::Fixed(1_000), Size::Fit)
+                        .padding(Insets::uniform(padding))
+                        .vertical(Align::Center),
+                );
+                let child = |align| {
+                    Layout::new(Size::Fixed(100), Size::Fixed(100))
+                        .margin(Insets::uniform(margin))
+                        .align_self(align)
+                };
+                let start = arena.insert_child(child(AlignSelf::Start), root);
+                let center =
+                    arena.insert_child(Layout::new(Size::Fixed(100), Size::Fixed(100)), root);
+                let end = arena.insert_child(child(AlignSelf::End), root);
+                let fixed = arena.insert_child(child(AlignSelf::Stretch), root);
+                let stretch = arena.insert_child(
+                    Layout::default()
+                        .margin(Insets::uniform(margin))
+                        .align_self(AlignSelf::Stretch),
+                    root,
+                );
+                arena.compute_layout(root);
+
+                assert_eq!(arena.rects[start].x(), padding + margin);
+                assert_eq!(arena.rects[center].x(), 450);
+                assert_eq!(arena.rects[end].x(), 1_000 - padding - margin - 100);
+
+                // Stretch only applies to fit sizes
+                assert_eq!(arena.rects[fixed].width(), 100);
+                assert_eq!(arena.rects[fixed].x(), padding + margin);
+                assert_eq!(
+                    arena.rects[stretch].width(),
+                    1_000 - 2 * padding - 2 * margin
+                );
+                assert_eq!(arena.rects[stretch].x(), padding + margin);
+            }
+        }
+
+        // Within grid cells
+        let mut arena = Arena::new();
+        let root = arena.insert(Layout::default().grid(
+            Grid::new(vec![Size::Fixed(100)], vec![Size::Fixed(100); 2]),
+            Align::Center,
+        ));
+        let end = arena.insert_child(
+            Layout::new(Size::Fixed(10), Size::Fixed(10)).align_self(AlignSelf::End),
+            root,
+        );
+        let stretch = arena.insert_child(Layout::default().align_self(AlignSelf::Stretch), root);
+        arena.compute_layout(root);
+
+        assert_eq!((arena.rects[end].x(), arena.rects[end].y()), (90, 90));
+        assert_eq!(arena.rects[stretch].size, [100, 100]);
+        assert_eq!(arena.rects[stretch].position, [0, 100]);
+    }
+
+    #[test]
+    fn test_wrap() {
+        for &padding in SIZES {
+            for &gap in [0, 5, 10].iter() {
+                let mut arena = Arena::new();
+                let root = arena.insert(
+                    Layout::new(Size::Fixed(300 + 2 * padding), Size::Fit)
+                        .padding(Insets::uniform(padding))
+                        .gap(Gap::Fixed(10))
+                        .horizontal(Align::Center)
+                        .wrap(Gap::Fixed(gap)),
+                );
+                let chips: Vec<_> = [(100, 20), (100, 30), (100, 20), (50, 40)]
+                    .into_iter()
+                    .map(|(w, h)| {
+                        arena.insert_child(Layout::new(Size::Fixed(w), Size::Fixed(h)), root)
+                    })
+                    .collect();
+                // Fills the rest of the second line
+                let expand =
+                    arena.insert_child(Layout::new(Size::Expand(1.0), Size::Expand(1.0)), root);
+                arena.compute_layout(root);
+
+                // Lines: [100, 100] (30px high), [100, 50, expand] (40px high)
+                assert_eq!(arena.rects[root].height(), 30 + gap + 40 + 2 * padding);
+
+                let positions: Vec<_> =
+                    chips.iter().map(|&idx| arena.rects[idx].position).collect();
+                let line2 = padding + 30 + gap;
+                assert_eq!(
+                    positions,
+                    vec![
+                        [padding, padding + 5],
+                        [padding + 110, padding],
+                        [padding, line2 + 10],
+                        [padding + 110, line2],
+                    ]
+                );
+
+                let expand = &arena.rects[expand];
+                assert_eq!(expand.size, [300 - 170, 40]);
+                assert_eq!(expand.position, [padding + 170, line2]);
+            }
+        }
+
+        // Fit parents wrap when clipped to their max width, with auto gaps between lines
+        let mut arena = Arena::new();
+        let root = arena.insert(Layout::new(Size::Fixed(1_000), Size::Fixed(1_000)));
+        let wrap = arena.insert_child(
+            Layout::new(Size::Fit, Size::Expand(1.0))
+                .max_width(250)
+                .horizontal(Align::Start)
+                .wrap(Gap::Auto),
+            root,
+        );
+        let children: Vec<_> = (0..5)
+            .map(|_| arena.insert_child(Layout::new(Size::Fixed(100), Size::Fixed(100)), wrap))
+            .collect();
+        arena.compute_layout(root);
+
+        assert_eq!(arena.rects[wrap].size, [250, 1_000]);
+        let positions: Vec<_> = children
+            .iter()
+            .map(|&idx| arena.rects[idx].position)
+            .collect();
+        assert_eq!(
+            positions,
+            vec![[0, 0], [100, 0], [0, 450], [100, 450], [0, 900]]
+        );
+
+        // Auto gaps within a line holding a single child
+        let mut arena = Arena::new();
+        let root = arena.insert(
+            Layout::new(Size::Fixed(100), Size::Fit)
+                .gap(Gap::Auto)
+                .horizontal(Align::Start)
+                .wrap(Gap::Fixed(0)),
+        );
+        let children: Vec<_> = (0..3)
+            .map(|_| arena.insert_child(Layout::new(Size::Fixed(40), Size::Fixed(40)), root))
+            .collect();
+        arena.compute_layout(root);
+
+        let positions: Vec<_> = children
+            .iter()
+            .map(|&idx| arena.rects[idx].position)
+            .collect();
+        assert_eq!(positions, vec![[0, 0], [60, 0], [0, 40]]);
+    }
+
+    #[test]
+    fn test_anchor() {
+        for &padding in SIZES {
+            let mut arena = Arena::new();
+            let root = arena.insert(Layout::new(Size::Fixed(1_000), Size::Fixed(1_000)));
+            let parent = arena.insert_child(
+                Layout::default()
+                    .padding(Insets::uniform(padding))
+                    .gap(Gap::Auto)
+                    .horizontal(Align::Start),
+                root,
+            );
+            let first = arena.insert_child(Layout::new(Size::Fixed(50), Size::Fixed(50)), parent);
+            // Badge centered on the top-right corner of the parent
+            let badge = arena.insert_child(
+                Layout::new(Size::Fixed(20), Size::Fixed(20)).anchor(Anchor::parent(
+                    [Align::End, Align::Start],
+                    [Align::Center; 2],
+                )),
+                parent,
+            );
+            let second = arena.insert_child(Layout::new(Size::Fixed(50), Size::Fixed(50)), parent);
+            // Overlay filling the parent
+            let overlay = arena.insert_child(
+                Layout::new(Size::Expand(1.0), Size::Expand(1.0))
+                    .anchor(Anchor::parent([Align::Start; 2], [Align::Start; 2])),
+                parent,
+            );
+            // Dropdown below the first child, declared before it is positioned
+            let dropdown = arena.insert_child(
+                Layout::new(Size::Fixed(100), Size::Fixed(200)).anchor(
+                    Anchor::node(first, [Align::Start, Align::End], [Align::Start; 2]).offset(0, 4),
+                ),
+                root,
+            );
+            // Floating button in the bottom-right corner of the root
+            let button = arena.insert_child(
+                Layout::new(Size::Fixed(40), Size::Fixed(40))
+                    .margin(Insets::uniform(16))
+                    .anchor(Anchor::parent([Align::End; 2], [Align::End; 2])),
+                root,
+            );
+            arena.compute_layout(root);
+
+            // Anchored children are out of the parent flow
+            let size = 100 + 2 * padding;
+            assert_eq!(arena.rects[parent].size, [size, 50 + 2 * padding]);
+            assert_eq!(arena.rects[second].x(), padding + 50);
+
+            assert_eq!(arena.rects[badge].position, [size - 10, -10]);
+            assert_eq!(arena.rects[overlay].size, [size, 50 + 2 * padding]);
+            assert_eq!(arena.rects[overlay].position, [0, 0]);
+            assert_eq!(arena.rects[dropdown].position, [padding, padding + 54]);
+            assert_eq!(arena.rects[button].position, [944, 944]);
+        }
+
+        // Anchored nodes are positioned after their targets, even when targets are anchored
+        // nodes (or their children) declared later
+        let mut arena = Arena::new();
+        let root = arena.insert(Layout::new(Size::Fixed(1_000), Size::Fixed(1_000)));
+        let button = arena.insert_child(Layout::new(Size::Fixed(50), Size::Fixed(50)), root);
+        let tooltip = arena.insert_child(Layout::new(Size::Fixed(30), Size::Fixed(10)), root);
+        let dropdown = arena.insert_child(
+            Layout::new(Size::Fixed(100), Size::Fit)
+                .padding(Insets::uniform(5))
+                .anchor(Anchor::node(button, [Align::End; 2], [Align::Start; 2])),
+            root,
+        );
+        let item = arena.insert_child(Layout::new(Size::Fixed(90), Size::Fixed(20)), dropdown);
+        arena.layouts[tooltip].anchor = Some(Anchor::node(
+            item,
+            [Align::End, Align::Start],
+            [Align::Start; 2],
+        ));
+        arena.compute_layout(root);
+
+        assert_eq!(arena.rects[dropdown].position, [50, 50]);
+        assert_eq!(arena.rects[item].position, [55, 55]);
+        assert_eq!(arena.rects[tooltip].position, [145, 55]);
+    }
+
+    #[test]
+    fn test_scroll() {
+        for padding in [0, 10] {
+            let mut arena = Arena::new();
+            let root = arena.insert(Layout::new(Size::Fixed(100), Size::Fixed(100)));
+            let list = arena.insert_child(
+                Layout::new(Size::Expand(1.0), Size::Expand(1.0))
+                    .vertical(Align::Start)
+                    .padding(Insets::uniform(padding))
+                    .scroll(false, true),
+                root,
+            );
+            let items: Vec<_> = (0..3)
+                .map(|_| arena.insert_child(Layout::new(Size::Expand(1.0), Size::Fixed(50)), list))
+                .collect();
+            arena.compute_layout(root);
+
+            // Content extent includes the overflowing children and padding
+            assert_eq!(arena.rects[list].size, [100, 100]);
+            assert_eq!(arena.rects[list].content, [100, 150 + 2 * padding]);
+            assert_eq!(arena.rects[items[0]].y(), padding);
+
+            // Children are moved by the scroll offset
+            arena.rects[list].scroll = [20, 30];
+            compute_positions(root, &arena.layouts, &mut arena.rects, &arena.children);
+            assert_eq!(arena.rects[list].scroll, [0, 30]);
+            assert_eq!(arena.rects[items[0]].position, [padding, padding - 30]);
+            assert_eq!(arena.rects[items[2]].y(), padding + 70);
+
+            // The scroll offset is clamped to the overflow
+            arena.rects[list].scroll = [0, 500];
+            compute_positions(root, &arena.layouts, &mut arena.rects, &arena.children);
+            assert_eq!(arena.rects[list].scroll, [0, 50 + 2 * padding]);
+            assert_eq!(arena.rects[items[2]].y(), 50 - padding);
+        }
+    }
+
+    #[test]
+    fn test_aspect_ratio() {
+        let mut arena = Arena::new();
+        let root = arena.insert(Layout::new(Size::Fixed(400), Size::Fixed(300)));
+        let row = arena.insert_child(
+            Layout::new(Size::Fixed(400), Size::Fit)
+                .horizontal(Align::Start)
+                .padding(Insets::uniform(10)),
+            root,
+        );
+        // Expand height is ignored in favor of the aspect ratio
+        let card = arena.insert_child(
+            Layout::new(Size::Expand(1.0), Size::Expand(1.0)).aspect_ratio(2.0),
+            row,
+        );
+        // Derived heights are still clipped
+        let preview = arena.insert_child(
+            Layout::new(Size::Expand(1.0), Size::Fit)
+                .aspect_ratio(16.0 / 9.0)
+                .max_height(80),
+            row,
+        );
+        arena.compute_layout(root);
+
+        assert_eq!(arena.rects[card].size, [190, 95]);
+        assert_eq!(arena.rects[preview].size, [190, 80]);
+        // Fit parents account for derived heights
+        assert_eq!(arena.rects[row].size, [400, 115]);
+    }
+
+    #[test]
+    fn test_relayout() {
+        let mut arena = Arena::new();
+        let root = arena.insert(Layout::new(Size::Fixed(400), Size::Fixed(300)));
+        let row = arena.insert_child(
+            Layout::new(Size::Fixed(400), Size::Fit).horizontal(Align::Start),
+            root,
+        );
+        let fixed = arena.insert_child(Layout::new(Size::Fixed(100), Size::Fixed(50)), row);
+        let expand = arena.insert_child(Layout::new(Size::Expand(1.0), Size::Expand(1.0)), row);
+        arena.compute_layout(root);
+        let rects = arena.rects.clone();
+
+        // Laying out again gives the same result
+        arena.compute_layout(root);
+        assert_eq!(arena.rects, rects);
+
+        // Laying out a subtree only updates its descendants
+        arena.layouts[fixed] = Layout::new(Size::Fixed(150), Size::Fixed(50));
+        arena.compute_layout(row);
+        assert_eq!(arena.rects[row], rects[row]);
+        assert_eq!(arena.rects[expand].size, [250, 50]);
+        assert_eq!(arena.rects[expand].x(), 150);
+    }
 }
@@ -4,6 +4,10 @@
 pub struct Rect {
     pub size: [i32; 2],
     pub position: [i32; 2],
+    /// Extent of the content (children & padding), at least as large as the size (viewport)
+    pub content: [i32; 2],
+    /// Scroll offset of the content, clamped to the overflow along scrollable axes
+    pub scroll: [i32; 2],
 }
 
 impl Rect {
@@ -22,4 +26,9 @@ impl Rect {
     pub fn y(&self) -> i32 {
         self.position[1]
     }
+
+    /// Amount of content that does not fit in the viewport along the given dimension
+    pub fn overflow(&self, dim: usize) -> i32 {
+        self.content[dim] - self.size[dim]
+    }
 }
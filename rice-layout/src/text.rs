@@ -0,0 +1,121 @@
+//! Text measurement & wrapping
+
+use std::ops::Range;
+
+/// Glyph metrics provider used to measure and wrap text
+pub trait Measure {
+    /// Horizontal advance of a character, in pixels
+    fn advance(&self, c: char) -> i32;
+
+    /// Height of a single line of text, in pixels
+    fn line_height(&self) -> i32;
+
+    /// Width of a string laid out on a single line, in pixels
+    fn width(&self, text: &str) -> i32 {
+        text.chars().map(|c| self.advance(c)).sum()
+    }
+}
+
+/// Fixed-width glyph metrics, for tests or bitmap fonts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Monospace {
+    pub advance: i32,
+    pub line_height: i32,
+}
+
+impl Monospace {
+    pub fn new(advance: i32, line_height: i32) -> Self {
+        Self {
+            advance,
+            line_height,
+        }
+    }
+}
+
+impl Measure for Monospace {
+    fn advance(&self, _: char) -> i32 {
+        self.advance
+    }
+
+    fn line_height(&self) -> i32 {
+        self.line_height
+    }
+}
+
+/// Width of the widest line of a text, without wrapping
+pub fn text_width(text: &str, measure: &dyn Measure) -> i32 {
+    text.split('\n')
+        .map(|line| measure.width(line))
+        .max()
+        .unwrap_or(0)
+}
+
+/// Wrap a text to the given width, and return the byte ranges of each line.
+///
+/// Lines are broken at whitespaces, and explicit line breaks are preserved.
+/// Words wider than the available width are not split, and overflow on their own line.
+pub fn wrap_text(text: &str, width: i32, measure: &dyn Measure) -> Vec<Range<usize>> {
+    let mut lines = Vec::new();
+    let mut offset = 0;
+
+    for hard_line in text.split('\n') {
+        let mut line: Option<Range<usize>> = None;
+        let mut line_width = 0;
+
+        for word in words(hard_line) {
+            let word = offset + word.start..offset + word.end;
+            let word_width = measure.width(&text[word.clone()]);
+
+            match &mut line {
+                // First word of the line
+                None => {
+                    line = Some(word);
+                    line_width = word_width;
+                }
+                Some(current) => {
+                    // Whitespaces between the previous word and this one
+                    let spacing = measure.width(&text[current.end..word.start]);
+
+                    if line_width + spacing + word_width <= width {
+                        current.end = word.end;
+                        line_width += spacing + word_width;
+                    } else {
+                        lines.push(current.clone());
+                        line = Some(word);
+                        line_width = word_width;
+                    }
+                }
+            }
+        }
+
+        // Empty lines still take vertical space
+        lines.push(line.unwrap_or(offset..offset));
+        offset += hard_line.len() + 1;
+    }
+
+    lines
+}
+
+/// Height of a text once wrapped to the given width
+pub fn text_height(text: &str, width: i32, measure: &dyn Measure) -> i32 {
+    wrap_text(text, width, measure).len() as i32 * measure.line_height()
+}
+
+/// Byte ranges of whitespace-separated words in a line
+fn words(line: &str) -> impl Iterator<Item = Range<usize>> + '_ {
+    let mut start = None;
+
+    line.char_indices()
+        .chain(std::iter::once((line.len(), ' ')))
+        .filter_map(move |(i, c)| match (c.is_whitespace(), start) {
+            (true, Some(s)) => {
+                start = None;
+                Some(s..i)
+            }
+            (false, None) => {
+                start = Some(i);
+                None
+            }
+            _ => None,
+        })
+}
@@ -1,6 +1,11 @@
 //! Computation utilities
 
-use crate::{Align, Direction, Gap, Layout, Rect, Size};
+use std::ops::Range;
+
+use crate::{
+    Align, AlignSelf, Anchor, Direction, Gap, Justify, Layout, Rect, Size,
+    text::{Measure, text_height, text_width},
+};
 
 /// Compute fixed & percent sizes for a component along a given dimension
 pub fn fixed(
@@ -11,13 +16,17 @@ pub fn fixed(
     dim: usize,
 ) {
     // 1. Set the current element's fixed size
-    if let Size::Fixed(size) = layouts[node].size[dim] {
+    if let Size::Fixed(size) = layouts[node].size[dim]
+        && !layouts[node].derived(dim)
+    {
         rects[node].size[dim] = layouts[node].clip_size(dim, size);
     }
 
     // 2. Compute percent sizes for children
     for &idx in &children[node] {
-        if let Size::Percent(fr) = layouts[idx].size[dim] {
+        if let Size::Percent(fr) = layouts[idx].size[dim]
+            && !layouts[idx].derived(dim)
+        {
             let space = rects[node].size[dim]
                 - layouts[node].padding.start[dim]
                 - layouts[node].padding.end[dim];
@@ -77,40 +86,93 @@ pub fn fit_across(
     rects[node].size[dim] = layouts[node].clip_size(dim, total + max_child_size);
 }
 
+/// Compute the fit width of a text component, as its widest line without wrapping
+pub fn fit_text_width(
+    node: usize,
+    rects: &mut [Rect], // All rects
+    layouts: &[Layout], // All layouts
+    text: &str,         // Text content
+    measure: &dyn Measure,
+) {
+    let padding = layouts[node].padding.start[0] + layouts[node].padding.end[0];
+    let width = text_width(text, measure);
+
+    rects[node].size[0] = layouts[node].clip_size(0, padding + width);
+}
+
+/// Compute the fit height of a text component, once wrapped to its computed width
+pub fn fit_text_height(
+    node: usize,
+    rects: &mut [Rect], // All rects
+    layouts: &[Layout], // All layouts
+    text: &str,         // Text content
+    measure: &dyn Measure,
+) {
+    let layout = &layouts[node];
+    let width = rects[node].size[0] - layout.padding.start[0] - layout.padding.end[0];
+    let height = text_height(text, width, measure);
+
+    rects[node].size[1] =
+        layout.clip_size(1, layout.padding.start[1] + height + layout.padding.end[1]);
+}
+
+/// Compute the height of a component from its computed width and aspect ratio
+pub fn ratio_height(
+    node: usize,
+    rects: &mut [Rect], // All rects
+    layouts: &[Layout], // All layouts
+    ratio: f32,         // Width / height ratio
+) {
+    let height = (rects[node].size[0] as f32 / ratio).round() as i32;
+    rects[node].size[1] = layouts[node].clip_size(1, height);
+}
+
 /// Compute the expand sizes for a component's children along a given dimension
-///
-/// Computes the remaining size after all fixed / percent / fit components have been substracted,
-/// and shares it among the expandable components iteratively, to respect min/max constraints.
 pub fn expand_along(
     node: usize,
     rects: &mut [Rect],      // All rects
     layouts: &[Layout],      // All layouts
     children: &[Vec<usize>], // All children
     dim: usize,
+) {
+    expand_line(node, rects, layouts, &children[node], dim);
+}
+
+/// Compute the expand sizes for a line of a component's children along a given dimension
+///
+/// Computes the remaining size after all fixed / percent / fit components have been substracted,
+/// and shares it among the expandable components iteratively, to respect min/max constraints.
+fn expand_line(
+    node: usize,
+    rects: &mut [Rect], // All rects
+    layouts: &[Layout], // All layouts
+    line: &[usize],     // Children in the line
+    dim: usize,
 ) {
     let mut total_fr = 0.0;
     let mut indexes = Vec::new(); // Track indexes of expandable components
     let mut occupied = 0;
 
     // 1. Compute total shared fraction & expandable component indexes
-    for &idx in children[node].iter() {
+    for &idx in line {
         let layout = &layouts[idx];
 
         // Add margins
         occupied += layout.margin.start[dim] + layout.margin.end[dim];
 
-        // Track expendable, or add occupied size
+        // Track expendable (reset from any previous layout), or add occupied size
         match layout.size[dim] {
-            Size::Expand(fraction) => {
+            Size::Expand(fraction) if !layout.derived(dim) => {
                 total_fr += fraction;
                 indexes.push(idx);
+                rects[idx].size[dim] = 0;
             }
             _ => occupied += rects[idx].size[dim],
         }
     }
 
     let gap = match layouts[node].gap {
-        Gap::Fixed(size) => size * children[node].len().saturating_sub(1) as i32,
+        Gap::Fixed(size) => size * line.len().saturating_sub(1) as i32,
         Gap::Auto => 0,
     };
 
@@ -169,7 +231,7 @@ pub fn expand_across(
         rects[node].size[dim] - layouts[node].padding.start[dim] - layouts[node].padding.end[dim];
 
     for &idx in &children[node] {
-        if let Size::Expand(_) = layouts[idx].size[dim] {
+        if fills_across(&layouts[idx], dim) {
             rects[idx].size[dim] = layouts[idx].clip_size(
                 dim,
                 space - layouts[idx].margin.start[dim] - layouts[idx].margin.end[dim],
@@ -178,6 +240,81 @@ pub fn expand_across(
     }
 }
 
+/// Compute the in-flow children of each node in the subtree of the given root,
+/// leaving out anchored children. Nodes outside of the subtree are left empty.
+pub fn flow_children(root: usize, layouts: &[Layout], children: &[Vec<usize>]) -> Vec<Vec<usize>> {
+    let mut flow = vec![Vec::new(); children.len()];
+    let mut stack = vec![root];
+
+    while let Some(node) = stack.pop() {
+        flow[node] = children[node]
+            .iter()
+            .copied()
+            .filter(|&idx| layouts[idx].anchor.is_none())
+            .collect();
+        stack.extend(&children[node]);
+    }
+
+    flow
+}
+
+/// Compute the expand sizes for a component's anchored children along a given dimension
+///
+/// Anchored children are out of the layout flow, and expand to the whole box of their parent
+/// (which anchors are relative to), ignoring its padding.
+pub fn expand_anchored(
+    node: usize,
+    rects: &mut [Rect],      // All rects
+    layouts: &[Layout],      // All layouts
+    children: &[Vec<usize>], // All children
+    dim: usize,
+) {
+    let space = rects[node].size[dim];
+
+    for &idx in &children[node] {
+        if layouts[idx].anchor.is_some() && layouts[idx].expands(dim) {
+            rects[idx].size[dim] = layouts[idx].clip_size(
+                dim,
+                space - layouts[idx].margin.start[dim] - layouts[idx].margin.end[dim],
+            );
+        }
+    }
+}
+
+/// Compute the position of an anchored node from the box of its target
+/// (the given parent node if the anchor has no target)
+pub fn position_anchored(
+    node: usize,
+    parent: usize,
+    rects: &mut [Rect], // All rects
+    layouts: &[Layout], // All layouts
+    anchor: Anchor,
+) {
+    let target = anchor.target.unwrap_or(parent);
+    let layout = &layouts[node];
+
+    for dim in 0..2 {
+        let start = rects[target].position[dim];
+        let size = rects[target].size[dim];
+
+        // Target point
+        let point = match anchor.at[dim] {
+            Align::Start => start,
+            Align::Center => start + size / 2,
+            Align::End => start + size,
+        };
+
+        // Place the origin point of the node on the target point
+        let origin = match anchor.origin[dim] {
+            Align::Start => layout.margin.start[dim],
+            Align::Center => -rects[node].size[dim] / 2,
+            Align::End => -rects[node].size[dim] - layout.margin.end[dim],
+        };
+
+        rects[node].position[dim] = point + origin + anchor.offset[dim];
+    }
+}
+
 /// Compute the positions of a component's children in a top-down way,
 /// for all directions at once
 pub fn positions(
@@ -190,21 +327,82 @@ pub fn positions(
     let [along, across] = match layouts[node].direction {
         Direction::Horizontal(_) => [0_usize, 1_usize],
         Direction::Vertical(_) => [1_usize, 0_usize],
+        Direction::Grid(align) => return position_grid(node, rects, layouts, children, align),
     };
+
+    // Space across the layout direction, shared by all children unless they wrap into lines
+    let start = rects[node].position[across] + layouts[node].padding.start[across];
+    let space = rects[node].size[across]
+        - layouts[node].padding.start[across]
+        - layouts[node].padding.end[across];
+
+    if layouts[node].wraps() {
+        position_lines(node, rects, layouts, children, start, space);
+    } else {
+        let line = &children[node];
+        position_line(node, rects, layouts, line, [along, across], start, space);
+    }
+}
+
+/// Measure the content extent of a component from its in-flow children, then clamp its
+/// scroll offset to the overflow and move the children by it, along scrollable axes
+pub fn scroll_content(
+    node: usize,
+    rects: &mut [Rect],      // All rects
+    layouts: &[Layout],      // All layouts
+    children: &[Vec<usize>], // All children
+) {
+    for dim in 0..2 {
+        let start = rects[node].position[dim];
+        let end = children[node]
+            .iter()
+            .map(|&idx| {
+                rects[idx].position[dim] + rects[idx].size[dim] + layouts[idx].margin.end[dim]
+            })
+            .max()
+            .unwrap_or(start + layouts[node].padding.start[dim]);
+        let content = end - start + layouts[node].padding.end[dim];
+        rects[node].content[dim] = content.max(rects[node].size[dim]);
+
+        // Non-scrollable axes are never offset
+        let max = match layouts[node].scroll[dim] {
+            true => rects[node].overflow(dim),
+            false => 0,
+        };
+        let offset = rects[node].scroll[dim].clamp(0, max);
+        rects[node].scroll[dim] = offset;
+
+        for &idx in &children[node] {
+            rects[idx].position[dim] -= offset;
+        }
+    }
+}
+
+/// Compute the positions of a line of children, given the space across the layout direction
+fn position_line(
+    node: usize,
+    rects: &mut [Rect], // All rects
+    layouts: &[Layout], // All layouts
+    line: &[usize],     // Children in the line
+    [along, across]: [usize; 2],
+    start: i32, // Start of the line across the layout direction
+    space: i32, // Size of the line across the layout direction
+) {
     let align = layouts[node].direction.align();
 
-    // Compute gap between children along the layout direction
-    let gap = gap_along(layouts[node].gap, node, rects, layouts, children, along);
+    // Compute gap between children along the layout direction, and justification spacing
+    let gap = gap_along(layouts[node].gap, node, rects, layouts, line, along);
+    let (offset, spacing) = justify_along(layouts[node].justify, node, rects, layouts, line, along);
 
     // Starting position along the layout direction
-    let mut pos_along = rects[node].position[along] + layouts[node].padding.start[along];
+    let mut pos_along = rects[node].position[along] + layouts[node].padding.start[along] + offset;
 
-    for &idx in &children[node] {
+    for &idx in line {
         // Position across the direction (independent from other children)
-        rects[idx].position[across] = position_across(
-            align,
-            &layouts[node],
-            &rects[node],
+        rects[idx].position[across] = align_position(
+            child_align(&layouts[idx], align),
+            start,
+            space,
             &layouts[idx],
             &rects[idx],
             across,
@@ -213,74 +411,554 @@ pub fn positions(
         // Position along the direction (accumulate child after child)
         pos_along += layouts[idx].margin.start[along];
         rects[idx].position[along] = pos_along;
-        pos_along += rects[idx].size[along] + layouts[idx].margin.end[along] + gap;
+        pos_along += rects[idx].size[along] + layouts[idx].margin.end[along] + gap + spacing;
     }
 }
 
-/// Returns the position of a child element across the layout direction of its parent
-/// given the alignment layout
-pub fn position_across(
+/// Returns the position of a child element aligned within the given space along a dimension
+pub fn align_position(
     align: Align,
-    parent_layout: &Layout,
-    parent_rect: &Rect,
+    start: i32, // Start of the available space
+    space: i32, // Size of the available space
     child_layout: &Layout,
     child_rect: &Rect,
     dim: usize,
 ) -> i32 {
     match align {
-        // Stick to start: only parent padding & child margin
-        Align::Start => {
-            parent_rect.position[dim]
-                + parent_layout.padding.start[dim]
-                + child_layout.margin.start[dim]
-        }
-        // Center: center child according to its size + size within parent padding
+        // Stick to start: only child margin
+        Align::Start => start + child_layout.margin.start[dim],
+        // Center: center child according to its size + margins within the space
         Align::Center => {
-            parent_rect.position[dim]
-                + parent_layout.padding.start[dim]
+            start
                 + child_layout.margin.start[dim]
-                + (parent_rect.size[dim]
-                    - parent_layout.padding.start[dim]
-                    - parent_layout.padding.end[dim]
+                + (space
                     - child_rect.size[dim]
                     - child_layout.margin.start[dim]
                     - child_layout.margin.end[dim])
                     / 2
         }
-        // Stick to end: only parent padding & child margin
-        Align::End => {
-            parent_rect.position[dim] + parent_rect.size[dim]
-                - parent_layout.padding.end[dim]
-                - child_layout.margin.end[dim]
-                - child_rect.size[dim]
-        }
+        // Stick to end: only child margin
+        Align::End => start + space - child_layout.margin.end[dim] - child_rect.size[dim],
+    }
+}
+
+/// Returns the alignment of a child, from its own alignment or its parent's default alignment
+fn child_align(layout: &Layout, default: Align) -> Align {
+    match layout.align_self {
+        Some(AlignSelf::Start) | Some(AlignSelf::Stretch) => Align::Start,
+        Some(AlignSelf::Center) => Align::Center,
+        Some(AlignSelf::End) => Align::End,
+        None => default,
     }
 }
 
+/// Returns true if a child fills the available space across its parent layout direction
+/// (or within its grid cell): expandable children, and stretched fit children
+fn fills_across(layout: &Layout, dim: usize) -> bool {
+    let stretched = matches!(
+        (layout.size[dim], layout.align_self),
+        (Size::Fit, Some(AlignSelf::Stretch))
+    );
+    layout.expands(dim) || (stretched && !layout.derived(dim))
+}
+
 /// Returns the gap value between children in a layout, along the given dimension
 fn gap_along(
     gap: Gap,
+    node: usize,
+    rects: &[Rect],     // All rects
+    layouts: &[Layout], // All layouts
+    line: &[usize],     // Children in the line
+    dim: usize,
+) -> i32 {
+    match gap {
+        Gap::Fixed(size) => size,
+        Gap::Auto if line.len() > 1 => {
+            remaining_along(node, rects, layouts, line, dim) / (line.len() - 1) as i32
+        }
+        // Lone children, e.g. on the last line of a wrapping layout
+        Gap::Auto => 0,
+    }
+}
+
+/// Returns the starting offset and the additional spacing between children along the given
+/// dimension, distributing the space left after fixed gaps according to the justification
+fn justify_along(
+    justify: Justify,
+    node: usize,
+    rects: &[Rect],     // All rects
+    layouts: &[Layout], // All layouts
+    line: &[usize],     // Children in the line
+    dim: usize,
+) -> (i32, i32) {
+    // Auto gaps already fill the available space
+    let gap = match layouts[node].gap {
+        Gap::Fixed(size) => size,
+        Gap::Auto => return (0, 0),
+    };
+    if line.is_empty() {
+        return (0, 0);
+    }
+
+    let count = line.len() as i32;
+    let free = remaining_along(node, rects, layouts, line, dim) - gap * (count - 1);
+
+    match justify {
+        Justify::Start => (0, 0),
+        Justify::Center => (free / 2, 0),
+        Justify::End => (free, 0),
+        // Spacing is never negative when children overflow
+        Justify::SpaceAround => {
+            let spacing = free.max(0) / count;
+            (spacing / 2, spacing)
+        }
+        Justify::SpaceEvenly => {
+            let spacing = free.max(0) / (count + 1);
+            (spacing, spacing)
+        }
+    }
+}
+
+/// Returns the space left along the given dimension after removing padding,
+/// and children sizes with their margins
+fn remaining_along(
+    node: usize,
+    rects: &[Rect],     // All rects
+    layouts: &[Layout], // All layouts
+    line: &[usize],     // Children in the line
+    dim: usize,
+) -> i32 {
+    let mut remaining =
+        rects[node].size[dim] - layouts[node].padding.start[dim] - layouts[node].padding.end[dim];
+
+    // Remove children sizes with their margins
+    for idx in line {
+        let layout = &layouts[*idx];
+        let rect = &rects[*idx];
+        remaining -= layout.margin.start[dim] + rect.size[dim] + layout.margin.end[dim];
+    }
+
+    remaining
+}
+
+/// Break the children of a wrapping layout into lines along its (horizontal) direction,
+/// and return the ranges of children in each line
+///
+/// Expandable children count with their minimum size, so that lines stay the same
+/// once they have been expanded.
+pub fn wrap_lines(
     node: usize,
     rects: &[Rect],          // All rects
     layouts: &[Layout],      // All layouts
     children: &[Vec<usize>], // All children
+) -> Vec<Range<usize>> {
+    let space = rects[node].size[0] - layouts[node].padding.start[0] - layouts[node].padding.end[0];
+    let gap = match layouts[node].gap {
+        Gap::Fixed(size) => size,
+        Gap::Auto => 0,
+    };
+
+    let mut lines = Vec::new();
+    let mut start = 0;
+    let mut used = 0;
+
+    for (i, &idx) in children[node].iter().enumerate() {
+        let layout = &layouts[idx];
+        let size = match layout.size[0] {
+            Size::Expand(_) => layout.clip_size(0, 0),
+            _ => rects[idx].size[0],
+        };
+        let size = layout.margin.start[0] + size + layout.margin.end[0];
+
+        if i == start {
+            used = size;
+        } else if used + gap + size > space {
+            // Break the line before this child
+            lines.push(start..i);
+            start = i;
+            used = size;
+        } else {
+            used += gap + size;
+        }
+    }
+
+    if start < children[node].len() {
+        lines.push(start..children[node].len());
+    }
+
+    lines
+}
+
+/// Returns the size of a line of children across the layout direction (its largest child)
+///
+/// Expandable children are ignored, as they fill their line.
+fn line_size(
+    rects: &[Rect],     // All rects
+    layouts: &[Layout], // All layouts
+    line: &[usize],     // Children in the line
     dim: usize,
 ) -> i32 {
-    match gap {
+    line.iter()
+        .filter(|&&idx| !layouts[idx].expands(dim))
+        .map(|&idx| {
+            layouts[idx].margin.start[dim] + rects[idx].size[dim] + layouts[idx].margin.end[dim]
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+/// Compute a fit height for a wrapping component, from the sizes of its lines
+pub fn fit_lines(
+    node: usize,
+    rects: &mut [Rect],      // All rects
+    layouts: &[Layout],      // All layouts
+    children: &[Vec<usize>], // All children
+) {
+    let lines = wrap_lines(node, rects, layouts, children);
+    let gap = match layouts[node].wrap {
+        Some(Gap::Fixed(size)) => size * lines.len().saturating_sub(1) as i32,
+        _ => 0,
+    };
+
+    let total = layouts[node].padding.start[1]
+        + lines
+            .into_iter()
+            .map(|line| line_size(rects, layouts, &children[node][line], 1))
+            .sum::<i32>()
+        + gap
+        + layouts[node].padding.end[1];
+
+    // Set the size, clipped to min/max
+    rects[node].size[1] = layouts[node].clip_size(1, total);
+}
+
+/// Compute the expand widths for a wrapping component's children, line by line
+pub fn expand_lines_along(
+    node: usize,
+    rects: &mut [Rect],      // All rects
+    layouts: &[Layout],      // All layouts
+    children: &[Vec<usize>], // All children
+) {
+    for line in wrap_lines(node, rects, layouts, children) {
+        expand_line(node, rects, layouts, &children[node][line], 0);
+    }
+}
+
+/// Compute the expand heights for a wrapping component's children, which fill their line
+pub fn expand_lines_across(
+    node: usize,
+    rects: &mut [Rect],      // All rects
+    layouts: &[Layout],      // All layouts
+    children: &[Vec<usize>], // All children
+) {
+    for line in wrap_lines(node, rects, layouts, children) {
+        let line = &children[node][line];
+        let size = line_size(rects, layouts, line, 1);
+
+        for &idx in line {
+            if fills_across(&layouts[idx], 1) {
+                rects[idx].size[1] = layouts[idx].clip_size(
+                    1,
+                    size - layouts[idx].margin.start[1] - layouts[idx].margin.end[1],
+                );
+            }
+        }
+    }
+}
+
+/// Compute the positions of a wrapping component's children, line after line
+fn position_lines(
+    node: usize,
+    rects: &mut [Rect],      // All rects
+    layouts: &[Layout],      // All layouts
+    children: &[Vec<usize>], // All children
+    start: i32,              // Start of the lines across the layout direction
+    space: i32,              // Space for the lines across the layout direction
+) {
+    let lines = wrap_lines(node, rects, layouts, children);
+    let sizes: Vec<i32> = lines
+        .iter()
+        .map(|line| line_size(rects, layouts, &children[node][line.clone()], 1))
+        .collect();
+
+    let gap = match layouts[node].wrap {
+        Some(Gap::Fixed(size)) => size,
+        Some(Gap::Auto) if lines.len() > 1 => {
+            ((space - sizes.iter().sum::<i32>()) / (lines.len() - 1) as i32).max(0)
+        }
+        _ => 0,
+    };
+
+    let mut pos = start;
+    for (line, size) in lines.into_iter().zip(sizes) {
+        position_line(
+            node,
+            rects,
+            layouts,
+            &children[node][line],
+            [0, 1],
+            pos,
+            size,
+        );
+        pos += size + gap;
+    }
+}
+
+/// Compute the cell (column & row) and span of each child of a grid, in children order
+///
+/// Children with an explicit cell are placed first. Other children are then placed in the next
+/// free cells, row after row, with their column span clipped to the amount of columns.
+pub fn grid_cells(
+    node: usize,
+    layouts: &[Layout],      // All layouts
+    children: &[Vec<usize>], // All children
+) -> Vec<([usize; 2], [usize; 2])> {
+    let columns = layouts[node].grid.columns.len().max(1);
+    let mut occupied: Vec<Vec<bool>> = Vec::new(); // Indexed by row, then column
+    let mut cells = vec![([0, 0], [1, 1]); children[node].len()];
+
+    // 1. Place children with an explicit cell
+    for (i, &idx) in children[node].iter().enumerate() {
+        if let Some(position) = layouts[idx].cell.position {
+            let span = layouts[idx].cell.span.map(|span| span.max(1));
+            occupy_cells(&mut occupied, position, span);
+            cells[i] = (position, span);
+        }
+    }
+
+    // 2. Place other children in the next free cells
+    let mut cursor = 0; // Next candidate cell, in row-major order
+    for (i, &idx) in children[node].iter().enumerate() {
+        if layouts[idx].cell.position.is_some() {
+            continue;
+        }
+
+        let [span_x, span_y] = layouts[idx].cell.span;
+        let span = [span_x.clamp(1, columns), span_y.max(1)];
+
+        let position = loop {
+            let position = [cursor % columns, cursor / columns];
+            if position[0] + span[0] <= columns && !any_occupied(&occupied, position, span) {
+                break position;
+            }
+            cursor += 1;
+        };
+
+        occupy_cells(&mut occupied, position, span);
+        cells[i] = (position, span);
+        cursor += span[0];
+    }
+
+    cells
+}
+
+/// Compute the track sizes of a grid along a given dimension, given its inner space
+///
+/// Fixed & percent tracks are sized first, then fit tracks fit their largest child
+/// (children spanning several tracks grow the last fit track they span if needed),
+/// and expand tracks share the remaining space.
+pub fn grid_tracks(
+    node: usize,
+    rects: &[Rect],                     // All rects
+    layouts: &[Layout],                 // All layouts
+    children: &[Vec<usize>],            // All children
+    cells: &[([usize; 2], [usize; 2])], // Children cells
+    space: i32,
+    dim: usize,
+) -> Vec<i32> {
+    let defs = layouts[node].grid.tracks(dim);
+    let count = cells
+        .iter()
+        .map(|(position, span)| position[dim] + span[dim])
+        .max()
+        .unwrap_or(0)
+        .max(defs.len())
+        .max(1);
+    let track = |i: usize| defs.get(i).copied().unwrap_or(Size::Fit);
+
+    // 1. Fixed & percent tracks
+    let mut tracks: Vec<i32> = (0..count)
+        .map(|i| match track(i) {
+            Size::Fixed(size) => size,
+            Size::Percent(fr) => (space as f32 * fr) as i32,
+            Size::Fit | Size::Expand(_) => 0,
+        })
+        .collect();
+
+    // 2. Fit tracks, from children spanning a single track first
+    let gap = match layouts[node].grid.gap[dim] {
         Gap::Fixed(size) => size,
-        Gap::Auto => {
-            let mut remaining = rects[node].size[dim]
-                - layouts[node].padding.start[dim]
-                - layouts[node].padding.end[dim];
+        Gap::Auto => 0,
+    };
+    let mut spanning = Vec::new();
+
+    for (&idx, &(position, span)) in children[node].iter().zip(cells) {
+        if layouts[idx].expands(dim) {
+            continue; // Expandable children fill their cell
+        }
+        let size =
+            layouts[idx].margin.start[dim] + rects[idx].size[dim] + layouts[idx].margin.end[dim];
+
+        if span[dim] > 1 {
+            spanning.push((position[dim]..position[dim] + span[dim], size));
+        } else if let Size::Fit = track(position[dim]) {
+            tracks[position[dim]] = tracks[position[dim]].max(size);
+        }
+    }
+
+    for (range, size) in spanning {
+        let available: i32 =
+            tracks[range.clone()].iter().sum::<i32>() + gap * (range.len() - 1) as i32;
+        if let Some(i) = range.rev().find(|&i| track(i) == Size::Fit)
+            && size > available
+        {
+            tracks[i] += size - available;
+        }
+    }
 
-            // Remove children sizes with their margins
-            for idx in &children[node] {
-                let layout = &layouts[*idx];
-                let rect = &rects[*idx];
-                remaining -= layout.margin.start[dim] + rect.size[dim] + layout.margin.end[dim];
+    // 3. Expand tracks share the remaining space
+    let total_fr: f32 = (0..count)
+        .filter_map(|i| match track(i) {
+            Size::Expand(fr) => Some(fr),
+            _ => None,
+        })
+        .sum();
+    let remaining = space - tracks.iter().sum::<i32>() - gap * (count - 1) as i32;
+
+    if remaining > 0 && total_fr > 0.0 {
+        for (i, size) in tracks.iter_mut().enumerate() {
+            if let Size::Expand(fr) = track(i) {
+                *size = (remaining as f32 * fr / total_fr).round() as i32;
             }
+        }
+    }
+
+    tracks
+}
+
+/// Compute a fit size for a grid component along a given dimension, from its tracks
+pub fn fit_grid(
+    node: usize,
+    rects: &mut [Rect],      // All rects
+    layouts: &[Layout],      // All layouts
+    children: &[Vec<usize>], // All children
+    dim: usize,
+) {
+    let cells = grid_cells(node, layouts, children);
+    let tracks = grid_tracks(node, rects, layouts, children, &cells, 0, dim);
+    let gap = match layouts[node].grid.gap[dim] {
+        Gap::Fixed(size) => size * (tracks.len() - 1) as i32,
+        Gap::Auto => 0,
+    };
+
+    let total = layouts[node].padding.start[dim]
+        + tracks.iter().sum::<i32>()
+        + gap
+        + layouts[node].padding.end[dim];
+
+    // Set the size, clipped to min/max
+    rects[node].size[dim] = layouts[node].clip_size(dim, total);
+}
+
+/// Compute the expand sizes for a grid component's children along a given dimension
+///
+/// Expandable children fill the cells they span.
+pub fn expand_grid(
+    node: usize,
+    rects: &mut [Rect],      // All rects
+    layouts: &[Layout],      // All layouts
+    children: &[Vec<usize>], // All children
+    dim: usize,
+) {
+    let space =
+        rects[node].size[dim] - layouts[node].padding.start[dim] - layouts[node].padding.end[dim];
+    let cells = grid_cells(node, layouts, children);
+    let tracks = grid_tracks(node, rects, layouts, children, &cells, space, dim);
+    let gap = grid_gap(layouts[node].grid.gap[dim], space, &tracks);
+
+    for (&idx, &(position, span)) in children[node].iter().zip(&cells) {
+        if fills_across(&layouts[idx], dim) {
+            let range = position[dim]..position[dim] + span[dim];
+            let cell = tracks[range].iter().sum::<i32>() + gap * (span[dim] - 1) as i32;
+
+            rects[idx].size[dim] = layouts[idx].clip_size(
+                dim,
+                cell - layouts[idx].margin.start[dim] - layouts[idx].margin.end[dim],
+            );
+        }
+    }
+}
+
+/// Compute the positions of a grid component's children, aligned within their cells
+fn position_grid(
+    node: usize,
+    rects: &mut [Rect],      // All rects
+    layouts: &[Layout],      // All layouts
+    children: &[Vec<usize>], // All children
+    align: Align,
+) {
+    let cells = grid_cells(node, layouts, children);
+
+    // Compute the size & start position of each track, along both dimensions
+    let mut tracks = [Vec::new(), Vec::new()];
+    let mut offsets = [Vec::new(), Vec::new()];
+
+    for dim in 0..2 {
+        let space = rects[node].size[dim]
+            - layouts[node].padding.start[dim]
+            - layouts[node].padding.end[dim];
+        tracks[dim] = grid_tracks(node, rects, layouts, children, &cells, space, dim);
+        let gap = grid_gap(layouts[node].grid.gap[dim], space, &tracks[dim]);
+
+        let mut pos = rects[node].position[dim] + layouts[node].padding.start[dim];
+        for size in &tracks[dim] {
+            offsets[dim].push(pos);
+            pos += size + gap;
+        }
+    }
+
+    for (&idx, &(position, span)) in children[node].iter().zip(&cells) {
+        let align = child_align(&layouts[idx], align);
+
+        for dim in 0..2 {
+            let last = position[dim] + span[dim] - 1;
+            let start = offsets[dim][position[dim]];
+            let end = offsets[dim][last] + tracks[dim][last];
 
-            remaining / children[node].len().saturating_sub(1) as i32
+            rects[idx].position[dim] =
+                align_position(align, start, end - start, &layouts[idx], &rects[idx], dim);
+        }
+    }
+}
+
+/// Returns the gap value between tracks of a grid, given its inner space
+fn grid_gap(gap: Gap, space: i32, tracks: &[i32]) -> i32 {
+    match gap {
+        Gap::Fixed(size) => size,
+        Gap::Auto if tracks.len() > 1 => {
+            ((space - tracks.iter().sum::<i32>()) / (tracks.len() - 1) as i32).max(0)
+        }
+        Gap::Auto => 0,
+    }
+}
+
+/// Returns true if any of the cells in the given area is occupied
+fn any_occupied(occupied: &[Vec<bool>], position: [usize; 2], span: [usize; 2]) -> bool {
+    (position[1]..position[1] + span[1]).any(|row| {
+        (position[0]..position[0] + span[0])
+            .any(|column| occupied.get(row).and_then(|r| r.get(column)) == Some(&true))
+    })
+}
+
+/// Mark all cells in the given area as occupied
+fn occupy_cells(occupied: &mut Vec<Vec<bool>>, position: [usize; 2], span: [usize; 2]) {
+    if occupied.len() < position[1] + span[1] {
+        occupied.resize(position[1] + span[1], Vec::new());
+    }
+    for row in &mut occupied[position[1]..position[1] + span[1]] {
+        if row.len() < position[0] + span[0] {
+            row.resize(position[0] + span[0], false);
         }
+        row[position[0]..position[0] + span[0]].fill(true);
     }
 }
@@ -0,0 +1,330 @@
+//! Language features computed from the syntax tree of a document
+
+use rice_parser::{BUILTIN_COMPONENTS, BUILTIN_TYPES, PROPERTIES, Session, Severity};
+use serde_json::{Value, json};
+use tree_sitter::Node;
+
+use crate::position::to_range;
+
+// Completion item kinds
+const KIND_PROPERTY: u32 = 10;
+const KIND_VALUE: u32 = 12;
+const KIND_CLASS: u32 = 7;
+const KIND_ENUM: u32 = 13;
+const KIND_ENUM_MEMBER: u32 = 20;
+
+// Diagnostic severities
+const SEVERITY_ERROR: u32 = 1;
+const SEVERITY_WARNING: u32 = 2;
+
+/// Diagnostic of the parser for files without a root component
+const NO_ROOT: &str = "No root component found";
+
+/// Parse errors & warnings of a document, as LSP diagnostics
+pub fn diagnostics(session: &Session) -> Vec<Value> {
+    let content = session.content();
+
+    // Files with declarations only are component libraries, used without a root component
+    let root = session.tree().root_node();
+    let library = child(root, "component").is_none()
+        && (child(root, "component_decl").is_some() || child(root, "enum_decl").is_some());
+
+    session
+        .diagnostics()
+        .into_iter()
+        .filter(|diagnostic| !(library && diagnostic.message == NO_ROOT))
+        .map(|diagnostic| {
+            json!({
+                "range": to_range(content, diagnostic.range),
+                "severity": match diagnostic.severity {
+                    Severity::Error => SEVERITY_ERROR,
+                    Severity::Warning => SEVERITY_WARNING,
+                },
+                "source": "rice",
+                "message": diagnostic.message,
+            })
+        })
+        .collect()
+}
+
+/// Completion items at a byte offset: property values after a colon, else property names
+/// and class names
+pub fn completion(session: &Session, offset: usize) -> Vec<Value> {
+    let content = session.content();
+    let root = session.tree().root_node();
+    let line_start = content[..offset].rfind('\n').map_or(0, |i| i + 1);
+    let prefix = &content[line_start..offset];
+
+    let node = root
+        .descendant_for_byte_range(offset, offset)
+        .unwrap_or(root);
+    let container = ancestor(node, &["component", "component_decl"]);
+    let decl = container
+        .and_then(|container| classname(container, content))
+        .and_then(|name| find_decl(root, content, "component_decl", name));
+
+    // Property values
+    if let Some((propname, _)) = prefix.split_once(':') {
+        let propname = propname.trim().split('.').next().unwrap_or("");
+        return value_completion(root, content, decl, propname);
+    }
+
+    let mut items = Vec::new();
+
+    // Property types, within component declarations
+    if let Some(container) = container
+        && container.kind() == "component_decl"
+    {
+        for name in BUILTIN_TYPES {
+            items.push(json!({ "label": name, "kind": KIND_CLASS }));
+        }
+        for decl in declarations(root, "enum_decl") {
+            items.push(declaration_item(decl, content, KIND_ENUM));
+        }
+    }
+
+    // Properties of the enclosing component
+    if let Some(container) = container
+        && container.kind() == "component"
+    {
+        match decl {
+            Some(decl) => {
+                for prop in children(decl, "property_decl") {
+                    if let Some(name) = child(prop, "propname") {
+                        items.push(json!({
+                            "label": text(name, content),
+                            "kind": KIND_PROPERTY,
+                            "detail": child(prop, "classname").map(|c| text(c, content)),
+                            "documentation": docstring(prop, content),
+                        }));
+                    }
+                }
+            }
+            None => {
+                for property in PROPERTIES {
+                    items.push(json!({ "label": property.name, "kind": KIND_PROPERTY }));
+                }
+            }
+        }
+    }
+
+    // Child components
+    for name in BUILTIN_COMPONENTS {
+        items.push(json!({ "label": name, "kind": KIND_CLASS }));
+    }
+    for decl in declarations(root, "component_decl") {
+        items.push(declaration_item(decl, content, KIND_CLASS));
+    }
+
+    items
+}
+
+/// Completion items for the value of a property
+fn value_completion(root: Node, content: &str, decl: Option<Node>, propname: &str) -> Vec<Value> {
+    // Properties of declared components: enum variants or booleans
+    if let Some(decl) = decl {
+        let Some(prop) = find_prop_decl(decl, content, propname) else {
+            return Vec::new();
+        };
+        let type_name = child(prop, "classname").map_or("", |c| text(c, content));
+        if type_name == "Bool" {
+            return ["true", "false"]
+                .iter()
+                .map(|value| json!({ "label": value, "kind": KIND_VALUE }))
+                .collect();
+        }
+
+        return find_decl(root, content, "enum_decl", type_name)
+            .map(|decl| {
+                children(decl, "enum_variant_decl")
+                    .filter_map(|variant| {
+                        let identifier = child(variant, "identifier")?;
+                        Some(json!({
+                            "label": text(identifier, content),
+                            "kind": KIND_ENUM_MEMBER,
+                            "documentation": docstring(variant, content),
+                        }))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+    }
+
+    // Built-in properties
+    PROPERTIES
+        .iter()
+        .filter(|property| property.name == propname)
+        .flat_map(|property| property.identifiers.iter())
+        .map(|value| json!({ "label": value, "kind": KIND_VALUE }))
+        .collect()
+}
+
+/// Hover documentation at a byte offset, from the docstring of the hovered declaration
+pub fn hover(session: &Session, offset: usize) -> Option<Value> {
+    let content = session.content();
+    let node = session
+        .tree()
+        .root_node()
+        .named_descendant_for_byte_range(offset, offset)?;
+    let definition = definition_node(session, node)?;
+    let documentation = docstring(definition, content)?;
+
+    Some(json!({
+        "contents": { "kind": "markdown", "value": documentation },
+        "range": to_range(content, node.byte_range()),
+    }))
+}
+
+/// Location of the declaration of the class name, property or enum variant at a byte offset
+pub fn definition(session: &Session, uri: &str, offset: usize) -> Option<Value> {
+    let content = session.content();
+    let node = session
+        .tree()
+        .root_node()
+        .named_descendant_for_byte_range(offset, offset)?;
+    let definition = definition_node(session, node)?;
+
+    // Point to the declared name, rather than the whole declaration
+    let name = ["classname", "propname", "identifier"]
+        .iter()
+        .find_map(|kind| child(definition, kind))
+        .unwrap_or(definition);
+
+    Some(json!({
+        "uri": uri,
+        "range": to_range(content, name.byte_range()),
+    }))
+}
+
+/// Declaration node referred to by a class name, property name or identifier node
+fn definition_node<'t>(session: &'t Session, node: Node<'t>) -> Option<Node<'t>> {
+    let content = session.content();
+    let root = session.tree().root_node();
+    let parent = node.parent()?;
+
+    match node.kind() {
+        // Used or declared component, or enum type of a declared property
+        "classname" => match parent.kind() {
+            "component_decl" | "enum_decl" => Some(parent),
+            "property_decl" => find_decl(root, content, "enum_decl", text(node, content)),
+            _ => find_decl(root, content, "component_decl", text(node, content)),
+        },
+        // Property of a used declared component
+        "propname" => match parent.kind() {
+            "property_decl" => Some(parent),
+            _ => {
+                let decl = declared_component(root, content, parent)?;
+                find_prop_decl(decl, content, text(node, content))
+            }
+        },
+        // Property of the enclosing declaration, or enum variant given to a property of a
+        // used declared component
+        "identifier" => match parent.kind() {
+            "enum_variant_decl" => Some(parent),
+            "value" => {
+                if let Some(prop) = ancestor(parent, &["component_decl"])
+                    .and_then(|decl| find_prop_decl(decl, content, text(node, content)))
+                {
+                    return Some(prop);
+                }
+
+                let property = parent.parent()?;
+                let decl = declared_component(root, content, property)?;
+                let propname = text(child(property, "propname")?, content);
+                let prop = find_prop_decl(decl, content, propname)?;
+                let type_name = text(child(prop, "classname")?, content);
+                let decl = find_decl(root, content, "enum_decl", type_name)?;
+
+                children(decl, "enum_variant_decl").find(|variant| {
+                    child(*variant, "identifier")
+                        .is_some_and(|identifier| text(identifier, content) == text(node, content))
+                })
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Declaration of the component that a property node is given to
+fn declared_component<'t>(root: Node<'t>, content: &str, property: Node<'t>) -> Option<Node<'t>> {
+    let component = property.parent()?;
+    let name = classname(component, content)?;
+    find_decl(root, content, "component_decl", name)
+}
+
+/// Completion item for a declaration, documented with its docstring
+fn declaration_item(decl: Node, content: &str, kind: u32) -> Value {
+    json!({
+        "label": child(decl, "classname").map(|name| text(name, content)),
+        "kind": kind,
+        "documentation": docstring(decl, content),
+    })
+}
+
+/// Top-level declaration of the given kind & class name
+fn find_decl<'t>(
+    root: Node<'t>,
+    content: &str,
+    kind: &'static str,
+    name: &str,
+) -> Option<Node<'t>> {
+    declarations(root, kind).find(|decl| classname(*decl, content) == Some(name))
+}
+
+/// Property declaration of a component declaration
+fn find_prop_decl<'t>(decl: Node<'t>, content: &str, name: &str) -> Option<Node<'t>> {
+    children(decl, "property_decl").find(|prop| {
+        child(*prop, "propname").is_some_and(|propname| text(propname, content) == name)
+    })
+}
+
+/// Top-level declarations of the given kind
+fn declarations<'t>(root: Node<'t>, kind: &'static str) -> impl Iterator<Item = Node<'t>> {
+    children(root, kind)
+}
+
+/// Named children of a node with the given kind
+fn children<'t>(node: Node<'t>, kind: &'static str) -> impl Iterator<Item = Node<'t>> {
+    let mut cursor = node.walk();
+    let nodes: Vec<_> = node.named_children(&mut cursor).collect();
+    nodes.into_iter().filter(move |child| child.kind() == kind)
+}
+
+/// First named child of a node with the given kind
+fn child<'t>(node: Node<'t>, kind: &'static str) -> Option<Node<'t>> {
+    children(node, kind).next()
+}
+
+/// Closest ancestor of a node (or the node itself) with one of the given kinds
+fn ancestor<'t>(node: Node<'t>, kinds: &[&str]) -> Option<Node<'t>> {
+    let mut current = Some(node);
+    while let Some(node) = current {
+        if kinds.contains(&node.kind()) {
+            return Some(node);
+        }
+        current = node.parent();
+    }
+    None
+}
+
+/// Class name of a component or declaration
+fn classname<'c>(node: Node, content: &'c str) -> Option<&'c str> {
+    child(node, "classname").map(|name| text(name, content))
+}
+
+/// Docstring of a declaration, without its slashes
+fn docstring(node: Node, content: &str) -> Option<String> {
+    let docstring = child(node, "docstring")?;
+    let lines: Vec<_> = text(docstring, content)
+        .lines()
+        .map(|line| line.trim().trim_start_matches("///").trim())
+        .collect();
+
+    Some(lines.join("\n"))
+}
+
+/// Source text of a node
+fn text<'c>(node: Node, content: &'c str) -> &'c str {
+    &content[node.byte_range()]
+}
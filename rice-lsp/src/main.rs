@@ -0,0 +1,45 @@
+//! Language server for Rice files, over stdio.
+//!
+//! Publishes parse diagnostics, and provides completion, hover documentation, go to definition
+//! and formatting.
+
+mod analysis;
+mod position;
+mod server;
+mod transport;
+
+use std::io::{self, BufReader};
+
+use crate::server::Server;
+use crate::transport::read_message;
+
+fn main() {
+    let mut reader = BufReader::new(io::stdin().lock());
+    let mut writer = io::stdout().lock();
+    let mut server = Server::default();
+
+    loop {
+        let message = match read_message(&mut reader) {
+            Ok(Some(message)) => message,
+            Ok(None) => break,
+            Err(error) => {
+                eprintln!("Error reading message: {}", error);
+                break;
+            }
+        };
+
+        match server.handle(&message, &mut writer) {
+            Ok(true) => {}
+            Ok(false) => break,
+            Err(error) => {
+                eprintln!("Error writing message: {}", error);
+                break;
+            }
+        }
+    }
+
+    // Exit with an error if the client did not ask for a shutdown first
+    if !server.is_shutdown() {
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,53 @@
+//! Conversions between byte offsets and LSP positions (lines & UTF-16 characters)
+
+use std::ops::Range;
+
+use serde_json::{Value, json};
+
+/// LSP position of a byte offset
+pub fn to_position(content: &str, offset: usize) -> Value {
+    let before = &content[..offset];
+    let line = before.matches('\n').count();
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    let character = before[line_start..].encode_utf16().count();
+
+    json!({ "line": line, "character": character })
+}
+
+/// LSP range of a byte range
+pub fn to_range(content: &str, range: Range<usize>) -> Value {
+    json!({
+        "start": to_position(content, range.start),
+        "end": to_position(content, range.end),
+    })
+}
+
+/// Byte offset of an LSP position, clamped to its line
+pub fn to_offset(content: &str, position: &Value) -> usize {
+    let line = position["line"].as_u64().unwrap_or(0) as usize;
+    let character = position["character"].as_u64().unwrap_or(0) as usize;
+
+    // Start of the line
+    let mut offset = 0;
+    for _ in 0..line {
+        match content[offset..].find('\n') {
+            Some(i) => offset += i + 1,
+            None => return content.len(),
+        }
+    }
+
+    // Count UTF-16 code units until the character, or the end of the line
+    let mut units = 0;
+    for (i, c) in content[offset..].char_indices() {
+        if units >= character || c == '\n' {
+            return offset + i;
+        }
+        units += c.len_utf16();
+    }
+    content.len()
+}
+
+/// Byte range of an LSP range
+pub fn to_byte_range(content: &str, range: &Value) -> Range<usize> {
+    to_offset(content, &range["start"])..to_offset(content, &range["end"])
+}
@@ -0,0 +1,369 @@
+//! Language server state, and dispatch of the client messages
+
+use std::{collections::HashMap, io, io::Write};
+
+use rice_parser::Session;
+use serde_json::{Value, json};
+
+use crate::{
+    analysis,
+    position::{to_byte_range, to_offset, to_range},
+    transport::write_message,
+};
+
+// JSON-RPC error codes
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_REQUEST: i64 = -32600;
+
+/// Open documents, by URI
+#[derive(Default)]
+pub struct Server {
+    documents: HashMap<String, Session>,
+    /// Whether the client asked for a shutdown
+    shutdown: bool,
+}
+
+impl Server {
+    pub fn is_shutdown(&self) -> bool {
+        self.shutdown
+    }
+
+    /// Handle a message from the client, and write the response and notifications.
+    /// Returns false once the client asks to exit.
+    pub fn handle<W: Write>(&mut self, message: &Value, writer: &mut W) -> io::Result<bool> {
+        let method = message["method"].as_str().unwrap_or("");
+        let params = &message["params"];
+
+        // Notifications have no id, and no response
+        let Some(id) = message.get("id") else {
+            match method {
+                "exit" => return Ok(false),
+                "textDocument/didOpen" => {
+                    let document = &params["textDocument"];
+                    let uri = document["uri"].as_str().unwrap_or("").to_string();
+                    let text = document["text"].as_str().unwrap_or("").to_string();
+                    self.documents.insert(uri.clone(), Session::new(text));
+                    self.publish_diagnostics(&uri, writer)?;
+                }
+                "textDocument/didChange" => {
+                    let uri = params["textDocument"]["uri"].as_str().unwrap_or("");
+                    let Some(session) = self.documents.get_mut(uri) else {
+                        return Ok(true);
+                    };
+                    for change in params["contentChanges"].as_array().into_iter().flatten() {
+                        let text = change["text"].as_str().unwrap_or("");
+                        // Changes without range replace the whole document
+                        let range = match change.get("range") {
+                            Some(range) => to_byte_range(session.content(), range),
+                            None => 0..session.content().len(),
+                        };
+                        // Edits out of sync with the document are skipped
+                        if let Err(error) = session.edit(range, text) {
+                            eprintln!("Error applying change to {}: {}", uri, error);
+                        }
+                    }
+                    self.publish_diagnostics(uri, writer)?;
+                }
+                "textDocument/didClose" => {
+                    let uri = params["textDocument"]["uri"].as_str().unwrap_or("");
+                    self.documents.remove(uri);
+                    // Clear the diagnostics of the closed document
+                    notify(
+                        writer,
+                        "textDocument/publishDiagnostics",
+                        json!({ "uri": uri, "diagnostics": [] }),
+                    )?;
+                }
+                // Ignore the rest (initialized, ...)
+                _ => {}
+            }
+            return Ok(true);
+        };
+
+        if self.shutdown && method != "exit" {
+            respond_error(writer, id, INVALID_REQUEST, "Server is shut down")?;
+            return Ok(true);
+        }
+
+        let result = match method {
+            "initialize" => json!({
+                "capabilities": {
+                    "textDocumentSync": { "openClose": true, "change": 2 },
+                    "completionProvider": { "triggerCharacters": [":", " "] },
+                    "hoverProvider": true,
+                    "definitionProvider": true,
+                    "documentFormattingProvider": true,
+                },
+                "serverInfo": { "name": "rice-lsp", "version": env!("CARGO_PKG_VERSION") },
+            }),
+            "shutdown" => {
+                self.shutdown = true;
+                Value::Null
+            }
+            "textDocument/completion" => self
+                .at_position(params, |session, offset| {
+                    Some(json!(analysis::completion(session, offset)))
+                })
+                .unwrap_or(Value::Null),
+            "textDocument/hover" => self
+                .at_position(params, analysis::hover)
+                .unwrap_or(Value::Null),
+            "textDocument/definition" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or("");
+                self.at_position(params, |session, offset| {
+                    analysis::definition(session, uri, offset)
+                })
+                .unwrap_or(Value::Null)
+            }
+            "textDocument/formatting" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or("");
+                self.documents
+                    .get(uri)
+                    .and_then(|session| {
+                        let content = session.content();
+                        // Documents that cannot be formatted are left untouched
+                        let formatted = rice_fmt::format_string(content).ok()?;
+                        Some(json!([{
+                            "range": to_range(content, 0..content.len()),
+                            "newText": formatted,
+                        }]))
+                    })
+                    .unwrap_or(Value::Null)
+            }
+            _ => {
+                respond_error(
+                    writer,
+                    id,
+                    METHOD_NOT_FOUND,
+                    &format!("Unknown method: {}", method),
+                )?;
+                return Ok(true);
+            }
+        };
+
+        write_message(
+            writer,
+            &json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        )?;
+        Ok(true)
+    }
+
+    /// Apply a feature to the document & byte offset of a text document position request
+    fn at_position(
+        &self,
+        params: &Value,
+        feature: impl FnOnce(&Session, usize) -> Option<Value>,
+    ) -> Option<Value> {
+        let uri = params["textDocument"]["uri"].as_str()?;
+        let session = self.documents.get(uri)?;
+        let offset = to_offset(session.content(), &params["position"]);
+        feature(session, offset)
+    }
+
+    /// Send the parse errors of a document to the client
+    fn publish_diagnostics<W: Write>(&self, uri: &str, writer: &mut W) -> io::Result<()> {
+        let Some(session) = self.documents.get(uri) else {
+            return Ok(());
+        };
+        notify(
+            writer,
+            "textDocument/publishDiagnostics",
+            json!({ "uri": uri, "diagnostics": analysis::diagnostics(session) }),
+        )
+    }
+}
+
+/// Send a notification to the client
+fn notify<W: Write>(writer: &mut W, method: &str, params: Value) -> io::Result<()> {
+    write_message(
+        writer,
+        &json!({ "jsonrpc": "2.0", "method": method, "params": params }),
+    )
+}
+
+/// Respond to a request with an error
+fn respond_error<W: Write>(writer: &mut W, id: &Value, code: i64, message: &str) -> io::Result<()> {
+    write_message(
+        writer,
+        &json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": { "code": code, "message": message },
+        }),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use crate::transport::read_message;
+
+    use super::*;
+
+    /// Messages written by the server while handling the given client messages, read back
+    /// as a client would
+    fn exchange(server: &mut Server, messages: &[Value]) -> Vec<Value> {
+        let mut output = Vec::new();
+        for message in messages {
+            server.handle(message, &mut output).unwrap();
+        }
+
+        let mut reader = Cursor::new(output);
+        std::iter::from_fn(|| read_message(&mut reader).unwrap()).collect()
+    }
+
+    /// Notification from the client
+    fn notification(method: &str, params: Value) -> Value {
+        json!({ "jsonrpc": "2.0", "method": method, "params": params })
+    }
+
+    /// Messages of the diagnostics published for a document
+    fn messages(notification: &Value) -> Vec<&str> {
+        assert_eq!(notification["method"], "textDocument/publishDiagnostics");
+        notification["params"]["diagnostics"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|diagnostic| diagnostic["message"].as_str().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_diagnostics() {
+        let mut server = Server::default();
+        let uri = "file:///button.rice";
+
+        let published = exchange(
+            &mut server,
+            &[notification(
+                "textDocument/didOpen",
+                json!({ "textDocument": { "uri": uri, "text": "Rect {\n  colour: #ff0000\n}" } }),
+            )],
+        );
+        assert_eq!(
+            messages(&published[0]),
+            vec!["Unknown property name: colour"]
+        );
+        assert_eq!(published[0]["params"]["uri"], uri);
+        assert_eq!(
+            published[0]["params"]["diagnostics"][0]["range"],
+            json!({
+                "start": { "line": 1, "character": 2 },
+                "end": { "line": 1, "character": 8 },
+            })
+        );
+
+        // Incremental changes are applied to the document
+        let published = exchange(
+            &mut server,
+            &[notification(
+                "textDocument/didChange",
+                json!({
+                    "textDocument": { "uri": uri },
+                    "contentChanges": [{
+                        "range": {
+                            "start": { "line": 1, "character": 2 },
+                            "end": { "line": 1, "character": 8 },
+                        },
+                        "text": "bg_color",
+                    }],
+                }),
+            )],
+        );
+        assert!(messages(&published[0]).is_empty());
+
+        // Declaration-only files do not need a root component, but must have valid declarations
+        let published = exchange(
+            &mut server,
+            &[notification(
+                "textDocument/didChange",
+                json!({
+                    "textDocument": { "uri": uri },
+                    "contentChanges": [{ "text": "component Card { Rect {} }" }],
+                }),
+            )],
+        );
+        assert!(messages(&published[0]).is_empty());
+
+        let published = exchange(
+            &mut server,
+            &[notification(
+                "textDocument/didChange",
+                json!({
+                    "textDocument": { "uri": uri },
+                    "contentChanges": [{ "text": "component Card { kind Shape Rect {} }" }],
+                }),
+            )],
+        );
+        assert_eq!(
+            messages(&published[0]),
+            vec!["Unknown property type: Shape"]
+        );
+
+        // Errors in declaration bodies are reported, even if they are never used
+        let published = exchange(
+            &mut server,
+            &[notification(
+                "textDocument/didChange",
+                json!({
+                    "textDocument": { "uri": uri },
+                    "contentChanges": [{ "text": "component Card { Rect { colour: #ffffff } }" }],
+                }),
+            )],
+        );
+        assert_eq!(
+            messages(&published[0]),
+            vec!["Unknown property name: colour"]
+        );
+        assert_eq!(published[0]["params"]["diagnostics"][0]["severity"], 1);
+
+        // Unused declarations of files with a root component are warnings
+        let published = exchange(
+            &mut server,
+            &[notification(
+                "textDocument/didChange",
+                json!({
+                    "textDocument": { "uri": uri },
+                    "contentChanges": [{ "text": "component Card { Rect {} } Rect {}" }],
+                }),
+            )],
+        );
+        assert_eq!(
+            messages(&published[0]),
+            vec!["Component Card is never used"]
+        );
+        assert_eq!(published[0]["params"]["diagnostics"][0]["severity"], 2);
+    }
+
+    #[test]
+    fn test_lifecycle() {
+        let mut server = Server::default();
+
+        let responses = exchange(
+            &mut server,
+            &[
+                json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {} }),
+                json!({ "jsonrpc": "2.0", "id": 2, "method": "unknown", "params": {} }),
+                json!({ "jsonrpc": "2.0", "id": 3, "method": "shutdown" }),
+                json!({ "jsonrpc": "2.0", "id": 4, "method": "textDocument/hover", "params": {} }),
+            ],
+        );
+        assert_eq!(responses[0]["id"], 1);
+        assert_eq!(
+            responses[0]["result"]["capabilities"]["hoverProvider"],
+            true
+        );
+        assert_eq!(responses[1]["error"]["code"], METHOD_NOT_FOUND);
+        assert_eq!(
+            responses[2],
+            json!({ "jsonrpc": "2.0", "id": 3, "result": null })
+        );
+        assert_eq!(responses[3]["error"]["code"], INVALID_REQUEST);
+        assert!(server.is_shutdown());
+
+        let mut output = Vec::new();
+        let exit = notification("exit", Value::Null);
+        assert!(!server.handle(&exit, &mut output).unwrap());
+    }
+}
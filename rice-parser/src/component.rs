@@ -1,24 +1,50 @@
 //! Component usage
 
 use core::str;
+use std::collections::HashMap;
 
 use rice_dom::{DOM, StyleSheet};
 use rice_layout::{Align, Direction, Layout};
 use tree_sitter::Node;
 
-use crate::properties::parse_property;
+use crate::{
+    Diagnostic,
+    declarations::{ComponentDecl, Scope, mistyped},
+    properties::parse_property,
+};
+
+/// Parse a component node and its children into the DOM, and return its index.
+/// Errors are reported in the diagnostics, and the faulty parts skipped.
+pub fn parse_component<'a>(
+    node: Node<'a>,
+    content: &'a [u8],
+    dom: &mut DOM,
+    scope: &Scope<'_, 'a>,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> usize {
+    // Declared components are instantiated from their declaration body
+    if let Some(classname) = node.named_child(0)
+        && classname.kind() == "classname"
+    {
+        let name = str::from_utf8(&content[classname.byte_range()]).unwrap();
+        if let Some(decl) = scope.components.get(name) {
+            return instantiate_component(node, name, decl, content, dom, scope, diagnostics);
+        }
+    }
 
-pub fn parse_component(node: Node, content: &[u8], dom: &mut DOM) -> usize {
     let mut layout = Layout::default();
     let mut stylesheet = StyleSheet::default();
     let mut children = Vec::new();
+    let mut class = "";
+    let mut image = None; // Image class name, which needs an image source
+    let mut src = false; // Whether an image source is given
 
     for child in node.named_children(&mut node.walk()) {
-        // TODO: check for error node
         match child.kind() {
             // Classname: set some default properties based on the component type
             "classname" => {
                 let name = str::from_utf8(&content[child.byte_range()]).unwrap();
+                class = name;
                 match name {
                     "Column" => {
                         layout.direction = Direction::Vertical(Align::default());
@@ -27,19 +53,238 @@ pub fn parse_component(node: Node, content: &[u8], dom: &mut DOM) -> usize {
                         layout.direction = Direction::Horizontal(Align::default());
                     }
                     "Rect" => {}
-                    _ => {
-                        // TODO: propagate an error instead
-                        panic!("Unknown component class name: {}", name);
+                    "Image" => image = Some(child),
+                    _ => diagnostics.push(Diagnostic::new(
+                        child,
+                        format!("Unknown component class name: {}", name),
+                    )),
+                }
+            }
+            "property" => {
+                src |= is_image_source(child, content);
+                parse_property(
+                    child,
+                    content,
+                    &mut layout,
+                    &mut stylesheet,
+                    scope,
+                    diagnostics,
+                );
+            }
+            "component" => children.push(parse_component(child, content, dom, scope, diagnostics)),
+
+            // Ignore the rest (syntax errors are reported separately)
+            _ => {}
+        }
+    }
+
+    if let Some(classname) = image
+        && !src
+    {
+        diagnostics.push(Diagnostic::new(
+            classname,
+            "Image component without a src property",
+        ));
+    }
+
+    let index = dom.insert_with_children(layout, stylesheet, children);
+    dom.classes[index] = class.to_string();
+    index
+}
+
+/// Whether a property node gives a default image source. Its value is checked separately,
+/// and may not be known yet (e.g. a property of a declared component).
+fn is_image_source(node: Node, content: &[u8]) -> bool {
+    let mut src = false;
+    for child in node.named_children(&mut node.walk()) {
+        let text = &content[child.byte_range()];
+        match child.kind() {
+            "propname" => src = text == b"src",
+            "identifier" if matches!(text, b"hover" | b"click") => return false,
+            _ => {}
+        }
+    }
+    src
+}
+
+/// Parse the body of a component declaration once, so that its errors are reported even if the
+/// component is never used. Properties are bound to their defaults, and those without one are
+/// left unchecked.
+pub fn validate_component_decl<'a>(
+    node: Node<'a>,
+    content: &'a [u8],
+    scope: &Scope<'_, 'a>,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    let Some(classname) = node
+        .named_children(&mut node.walk())
+        .find(|child| child.kind() == "classname")
+    else {
+        return;
+    };
+    let name = str::from_utf8(&content[classname.byte_range()]).unwrap();
+
+    // Duplicate declarations are not registered
+    let Some(decl) = scope.components.get(name) else {
+        return;
+    };
+    let Some(body) = decl.body.filter(|body| body.parent() == Some(node)) else {
+        return;
+    };
+
+    let inner = Scope {
+        components: scope.components,
+        enums: scope.enums,
+        decl: Some(decl),
+        props: decl
+            .props
+            .iter()
+            .filter_map(|prop| Some((prop.name, prop.default?)))
+            .collect(),
+        stack: vec![name],
+    };
+    parse_component(body, content, &mut DOM::new(), &inner, diagnostics);
+}
+
+/// Instantiate a declared component: bind the given properties (or their defaults), then parse
+/// the declaration body with them. Children given at the usage site are added to the body root.
+fn instantiate_component<'a>(
+    node: Node<'a>,
+    name: &'a str,
+    decl: &ComponentDecl<'a>,
+    content: &'a [u8],
+    dom: &mut DOM,
+    scope: &Scope<'_, 'a>,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> usize {
+    let mut props = HashMap::new();
+    let mut given = Vec::new(); // Given properties, including those without a known value
+    let mut children = Vec::new();
+
+    // 1. Bind the given properties, and parse children within the usage scope
+    for child in node.named_children(&mut node.walk()) {
+        match child.kind() {
+            "property" => {
+                if let Some((propname, value)) =
+                    bind_property(child, name, decl, content, scope, diagnostics)
+                {
+                    given.push(propname);
+                    if let Some(value) = value {
+                        props.insert(propname, value);
                     }
                 }
             }
-            "property" => parse_property(child, content, &mut layout, &mut stylesheet),
-            "component" => children.push(parse_component(child, content, dom)),
+            "component" => children.push(parse_component(child, content, dom, scope, diagnostics)),
+            _ => {}
+        }
+    }
+
+    // 2. Bind default values for the missing properties
+    for prop in &decl.props {
+        if given.contains(&prop.name) {
+            continue;
+        }
+        match prop.default {
+            Some(default) => {
+                props.insert(prop.name, default);
+            }
+            None => diagnostics.push(Diagnostic::new(
+                node,
+                format!("Missing property {} for component {}", prop.name, name),
+            )),
+        }
+    }
+
+    // 3. Parse the declaration body with the bound properties
+    let body = match decl.body {
+        Some(_) if scope.stack.contains(&name) => {
+            diagnostics.push(Diagnostic::new(
+                node,
+                format!("Component {} is used recursively", name),
+            ));
+            None
+        }
+        body => body,
+    };
+    let Some(body) = body else {
+        let index = dom.insert_with_children(Layout::default(), StyleSheet::default(), children);
+        dom.classes[index] = name.to_string();
+        return index;
+    };
 
-            // Ignore the rest
+    let mut stack = scope.stack.clone();
+    stack.push(name);
+    let inner = Scope {
+        components: scope.components,
+        enums: scope.enums,
+        decl: Some(decl),
+        props,
+        stack,
+    };
+
+    // The body root is named after the declared component
+    let index = parse_component(body, content, dom, &inner, diagnostics);
+    dom.append_children(index, children);
+    dom.classes[index] = name.to_string();
+    index
+}
+
+/// Bind a property given to a declared component, checking that it is declared & well typed.
+/// The value is none when it names a property of the enclosing component without a value.
+fn bind_property<'a>(
+    node: Node<'a>,
+    name: &str,
+    decl: &ComponentDecl<'a>,
+    content: &'a [u8],
+    scope: &Scope<'_, 'a>,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Option<(&'a str, Option<Node<'a>>)> {
+    let mut propname = None;
+    let mut value = None;
+
+    for child in node.named_children(&mut node.walk()) {
+        match child.kind() {
+            "propname" => propname = Some(child),
+            "value" => value = child.named_child(0),
+            "identifier" | "values" => {
+                diagnostics.push(Diagnostic::new(
+                    child,
+                    format!("Unexpected {} for a component property", child.kind()),
+                ));
+                return None;
+            }
             _ => {}
         }
     }
 
-    dom.insert_with_children(layout, stylesheet, children)
+    let propname = propname?;
+    let text = str::from_utf8(&content[propname.byte_range()]).unwrap();
+
+    let Some(prop) = decl.prop(text) else {
+        diagnostics.push(Diagnostic::new(
+            propname,
+            format!("Unknown property {} for component {}", text, name),
+        ));
+        return None;
+    };
+
+    let Some(value) = value else {
+        diagnostics.push(Diagnostic::new(
+            node,
+            format!("Expected a value for property {}", text),
+        ));
+        return None;
+    };
+
+    // Values may refer to the properties of an enclosing declared component
+    if scope.unbound(value, content) {
+        return Some((prop.name, None));
+    }
+    let value = scope.resolve(value, content);
+    if !prop.kind.accepts(value, content, scope.enums) {
+        diagnostics.push(mistyped(value, content, prop.type_name, text));
+        return None;
+    }
+
+    Some((prop.name, Some(value)))
 }
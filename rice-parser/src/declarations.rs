@@ -0,0 +1,379 @@
+//! Enum & component declarations, and the names they bring in scope
+
+use core::str;
+use std::collections::{HashMap, HashSet};
+
+use tree_sitter::Node;
+
+use crate::Diagnostic;
+
+/// Built-in component class names
+pub const BUILTIN_COMPONENTS: &[&str] = &["Column", "Row", "Rect", "Image"];
+
+/// Built-in property type names
+pub const BUILTIN_TYPES: &[&str] = &["Size", "Pixels", "Color", "Bool", "String"];
+
+/// Declared enums, with their variants
+pub type Enums<'a> = HashMap<&'a str, Vec<&'a str>>;
+
+/// Type of a declared component property
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PropType<'a> {
+    /// Layout size (pixels, fraction, percentage or `fit`)
+    Size,
+    /// Pixel amount
+    Pixels,
+    /// Hex color
+    Color,
+    /// `true` or `false`
+    Bool,
+    /// Quoted string
+    String,
+    /// Variant identifier of a declared enum
+    Enum(&'a str),
+}
+
+impl<'a> PropType<'a> {
+    /// Built-in type or declared enum from its class name
+    pub fn from_name(name: &'a str, enums: &Enums<'a>) -> Option<Self> {
+        match name {
+            "Size" => Some(PropType::Size),
+            "Pixels" => Some(PropType::Pixels),
+            "Color" => Some(PropType::Color),
+            "Bool" => Some(PropType::Bool),
+            "String" => Some(PropType::String),
+            _ if enums.contains_key(name) => Some(PropType::Enum(name)),
+            _ => None,
+        }
+    }
+
+    /// Whether a value node has this type
+    pub fn accepts(&self, value: Node, content: &[u8], enums: &Enums) -> bool {
+        match self {
+            PropType::Size => {
+                matches!(value.kind(), "pixels" | "fraction" | "percentage")
+                    || (value.kind() == "identifier" && &content[value.byte_range()] == b"fit")
+            }
+            PropType::Pixels => value.kind() == "pixels",
+            PropType::Color => value.kind() == "hex_color",
+            PropType::Bool => value.kind() == "boolean",
+            PropType::String => value.kind() == "string",
+            PropType::Enum(name) => {
+                let text = str::from_utf8(&content[value.byte_range()]).unwrap();
+                value.kind() == "identifier" && enums[name].contains(&text)
+            }
+        }
+    }
+}
+
+/// Property declared by a component
+#[derive(Debug, Clone)]
+pub struct PropDecl<'a> {
+    pub name: &'a str,
+    pub kind: PropType<'a>,
+    /// Type name, as written in the declaration
+    pub type_name: &'a str,
+    /// Default value node, if the property is optional
+    pub default: Option<Node<'a>>,
+}
+
+/// Component declared in a source file
+#[derive(Debug, Clone)]
+pub struct ComponentDecl<'a> {
+    pub props: Vec<PropDecl<'a>>,
+    /// Root component of the declaration body, instantiated for each use
+    pub body: Option<Node<'a>>,
+}
+
+impl<'a> ComponentDecl<'a> {
+    /// Declared property with the given name
+    pub fn prop(&self, name: &str) -> Option<&PropDecl<'a>> {
+        self.props.iter().find(|prop| prop.name == name)
+    }
+}
+
+/// Names available while parsing a component
+#[derive(Debug, Clone)]
+pub struct Scope<'s, 'a> {
+    /// Declared components, by class name
+    pub components: &'s HashMap<&'a str, ComponentDecl<'a>>,
+    /// Declared enums, by class name
+    pub enums: &'s Enums<'a>,
+    /// Declared component being instantiated
+    pub decl: Option<&'s ComponentDecl<'a>>,
+    /// Values bound to the properties of the declared component being instantiated
+    pub props: HashMap<&'a str, Node<'a>>,
+    /// Declared components being instantiated, to detect recursive uses
+    pub stack: Vec<&'a str>,
+}
+
+impl<'s, 'a> Scope<'s, 'a> {
+    /// Scope at the root of a source file
+    pub fn root(components: &'s HashMap<&'a str, ComponentDecl<'a>>, enums: &'s Enums<'a>) -> Self {
+        Self {
+            components,
+            enums,
+            decl: None,
+            props: HashMap::new(),
+            stack: Vec::new(),
+        }
+    }
+
+    /// Replace an identifier naming a bound property with its value
+    pub fn resolve(&self, value: Node<'a>, content: &[u8]) -> Node<'a> {
+        if value.kind() != "identifier" {
+            return value;
+        }
+        let name = str::from_utf8(&content[value.byte_range()]).unwrap();
+        self.props.get(name).copied().unwrap_or(value)
+    }
+
+    /// Whether the instantiated component declares a property with the given name
+    pub fn declares(&self, name: &str) -> bool {
+        self.decl.is_some_and(|decl| decl.prop(name).is_some())
+    }
+
+    /// Whether an identifier names a property of the instantiated component that has no value,
+    /// as when validating a declaration body on its own
+    pub fn unbound(&self, value: Node<'a>, content: &[u8]) -> bool {
+        if value.kind() != "identifier" {
+            return false;
+        }
+        let name = str::from_utf8(&content[value.byte_range()]).unwrap();
+        self.declares(name) && !self.props.contains_key(name)
+    }
+
+    /// Whether the enum property of the instantiated component that has the given variant
+    /// is bound to it, or none if no enum property has this variant
+    pub fn condition(&self, variant: &str, content: &[u8]) -> Option<bool> {
+        let prop = self.decl?.props.iter().find(|prop| match prop.kind {
+            PropType::Enum(name) => self.enums[name].contains(&variant),
+            _ => false,
+        })?;
+
+        Some(
+            self.props
+                .get(prop.name)
+                .is_some_and(|value| &content[value.byte_range()] == variant.as_bytes()),
+        )
+    }
+}
+
+/// Parse an enum declaration node, and register it with the declared enums
+pub fn parse_enum_decl<'a>(
+    node: Node<'a>,
+    content: &'a [u8],
+    enums: &mut Enums<'a>,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    let mut name = None;
+    let mut variants = Vec::new();
+
+    for child in node.named_children(&mut node.walk()) {
+        match child.kind() {
+            "classname" => name = Some(child),
+            "enum_variant_decl" => {
+                let Some(identifier) = child
+                    .named_children(&mut child.walk())
+                    .find(|c| c.kind() == "identifier")
+                else {
+                    continue;
+                };
+                let variant = str::from_utf8(&content[identifier.byte_range()]).unwrap();
+
+                if variants.contains(&variant) {
+                    diagnostics.push(Diagnostic::new(
+                        identifier,
+                        format!("Variant {} is already declared", variant),
+                    ));
+                } else {
+                    variants.push(variant);
+                }
+            }
+
+            // Ignore the rest (syntax errors are reported separately)
+            _ => {}
+        }
+    }
+
+    let Some(name) = name else {
+        return;
+    };
+    let text = str::from_utf8(&content[name.byte_range()]).unwrap();
+
+    if BUILTIN_TYPES.contains(&text) || enums.contains_key(text) {
+        diagnostics.push(Diagnostic::new(
+            name,
+            format!("Type {} is already defined", text),
+        ));
+        return;
+    }
+    enums.insert(text, variants);
+}
+
+/// Parse a component declaration node, and register it with the declared components
+pub fn parse_component_decl<'a>(
+    node: Node<'a>,
+    content: &'a [u8],
+    enums: &Enums<'a>,
+    components: &mut HashMap<&'a str, ComponentDecl<'a>>,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    let mut name = None;
+    let mut decl = ComponentDecl {
+        props: Vec::new(),
+        body: None,
+    };
+
+    for child in node.named_children(&mut node.walk()) {
+        match child.kind() {
+            "classname" => name = Some(child),
+            "property_decl" => {
+                if let Some(prop) = parse_property_decl(child, content, enums, &decl, diagnostics) {
+                    decl.props.push(prop);
+                }
+            }
+            "component" if decl.body.is_some() => diagnostics.push(Diagnostic::new(
+                child,
+                "Component declarations must have a single root component",
+            )),
+            "component" => decl.body = Some(child),
+
+            // Ignore the rest (syntax errors are reported separately)
+            _ => {}
+        }
+    }
+
+    let Some(name) = name else {
+        return;
+    };
+    let text = str::from_utf8(&content[name.byte_range()]).unwrap();
+
+    if decl.body.is_none() {
+        diagnostics.push(Diagnostic::new(
+            node,
+            format!("Component {} has no root component", text),
+        ));
+    }
+
+    if BUILTIN_COMPONENTS.contains(&text) || components.contains_key(text) {
+        diagnostics.push(Diagnostic::new(
+            name,
+            format!("Component {} is already defined", text),
+        ));
+        return;
+    }
+    components.insert(text, decl);
+}
+
+/// Parse a property declaration node, checking its type and default value
+fn parse_property_decl<'a>(
+    node: Node<'a>,
+    content: &'a [u8],
+    enums: &Enums<'a>,
+    decl: &ComponentDecl<'a>,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Option<PropDecl<'a>> {
+    let mut name = None;
+    let mut type_name = None;
+    let mut default = None;
+
+    for child in node.named_children(&mut node.walk()) {
+        match child.kind() {
+            "propname" => name = Some(child),
+            "classname" => type_name = Some(child),
+            "value" => default = child.named_child(0),
+            _ => {}
+        }
+    }
+
+    let (name, type_name) = (name?, type_name?);
+    let name_text = str::from_utf8(&content[name.byte_range()]).unwrap();
+    let type_text = str::from_utf8(&content[type_name.byte_range()]).unwrap();
+
+    if decl.prop(name_text).is_some() {
+        diagnostics.push(Diagnostic::new(
+            name,
+            format!("Property {} is already declared", name_text),
+        ));
+        return None;
+    }
+
+    let Some(kind) = PropType::from_name(type_text, enums) else {
+        diagnostics.push(Diagnostic::new(
+            type_name,
+            format!("Unknown property type: {}", type_text),
+        ));
+        return None;
+    };
+
+    if let Some(value) = default
+        && !kind.accepts(value, content, enums)
+    {
+        diagnostics.push(mistyped(value, content, type_text, name_text));
+        return None;
+    }
+
+    Some(PropDecl {
+        name: name_text,
+        kind,
+        type_name: type_text,
+        default,
+    })
+}
+
+/// Error for a value that does not have the type of its property
+pub fn mistyped(value: Node, content: &[u8], type_name: &str, propname: &str) -> Diagnostic {
+    let text = str::from_utf8(&content[value.byte_range()]).unwrap();
+    Diagnostic::new(
+        value,
+        format!(
+            "Expected {} for property {}, found {}",
+            type_name, propname, text
+        ),
+    )
+}
+
+/// Warn about the enums & components declared in a source file that are never used
+/// as a property type or a component class name
+pub fn unused_declarations(root: Node, content: &[u8], diagnostics: &mut Vec<Diagnostic>) {
+    let mut used = HashSet::new();
+    collect_uses(root, content, &mut used);
+
+    for node in root.named_children(&mut root.walk()) {
+        let kind = match node.kind() {
+            "enum_decl" => "Enum",
+            "component_decl" => "Component",
+            _ => continue,
+        };
+        let Some(name) = classname(node) else {
+            continue;
+        };
+        let text = str::from_utf8(&content[name.byte_range()]).unwrap();
+
+        if !used.contains(text) {
+            diagnostics.push(Diagnostic::warning(
+                name,
+                format!("{} {} is never used", kind, text),
+            ));
+        }
+    }
+}
+
+/// Collect the class names used by components & property declarations
+fn collect_uses<'a>(node: Node, content: &'a [u8], used: &mut HashSet<&'a str>) {
+    for child in node.named_children(&mut node.walk()) {
+        if matches!(child.kind(), "component" | "property_decl")
+            && let Some(name) = classname(child)
+        {
+            used.insert(str::from_utf8(&content[name.byte_range()]).unwrap());
+        }
+        collect_uses(child, content, used);
+    }
+}
+
+/// Class name child of a node
+fn classname(node: Node) -> Option<Node> {
+    node.named_children(&mut node.walk())
+        .find(|child| child.kind() == "classname")
+}
@@ -0,0 +1,91 @@
+//! Parse errors reported to the user
+
+use std::{fmt, ops::Range};
+
+use tree_sitter::Node;
+
+/// How serious a diagnostic is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Severity {
+    /// The file cannot be loaded
+    #[default]
+    Error,
+    /// The file can be loaded, but likely has a mistake (e.g. an unused declaration)
+    Warning,
+}
+
+/// Error or warning located in a Rice source file
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Diagnostic {
+    /// Byte range of the faulty source
+    pub range: Range<usize>,
+    /// Line of the start of the range (starting at 0)
+    pub line: usize,
+    /// Byte column of the start of the range (starting at 0)
+    pub column: usize,
+    pub severity: Severity,
+    /// Error message
+    pub message: String,
+}
+
+impl Diagnostic {
+    /// Create an error spanning the given node
+    pub fn new(node: Node, message: impl Into<String>) -> Self {
+        let start = node.start_position();
+        Self {
+            range: node.byte_range(),
+            line: start.row,
+            column: start.column,
+            severity: Severity::Error,
+            message: message.into(),
+        }
+    }
+
+    /// Create a warning spanning the given node
+    pub fn warning(node: Node, message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Warning,
+            ..Self::new(node, message)
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    /// Displayed with 1-based line & column numbers, as in editors
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (line, column) = (self.line + 1, self.column + 1);
+        match self.severity {
+            Severity::Error => write!(f, "{}:{}: {}", line, column, self.message),
+            Severity::Warning => write!(f, "{}:{}: warning: {}", line, column, self.message),
+        }
+    }
+}
+
+impl std::error::Error for Diagnostic {}
+
+/// Recursively collect syntax errors (error & missing nodes) from a parsed tree
+pub fn syntax_errors(node: Node, content: &[u8], diagnostics: &mut Vec<Diagnostic>) {
+    if node.is_error() {
+        let text = String::from_utf8_lossy(&content[node.byte_range()]);
+        diagnostics.push(Diagnostic::new(
+            node,
+            format!("Syntax error: unexpected `{}`", text.trim()),
+        ));
+        return;
+    }
+
+    if node.is_missing() {
+        diagnostics.push(Diagnostic::new(
+            node,
+            format!("Syntax error: missing `{}`", node.kind()),
+        ));
+        return;
+    }
+
+    // Only explore subtrees that contain errors
+    if node.has_error() {
+        for child in node.children(&mut node.walk()) {
+            syntax_errors(child, content, diagnostics);
+        }
+    }
+}
@@ -1,36 +1,704 @@
 //! Parse Rice source files into a DOM structure
 
 mod component;
+mod declarations;
+mod diagnostic;
 mod properties;
+mod reload;
+mod session;
 mod values;
 
+use std::collections::{HashMap, HashSet};
+
 use rice_dom::DOM;
+use tree_sitter::Tree;
+
+use crate::component::{parse_component, validate_component_decl};
+use crate::declarations::{Scope, parse_component_decl, parse_enum_decl, unused_declarations};
+use crate::diagnostic::syntax_errors;
 
-use crate::component::parse_component;
+pub use declarations::{BUILTIN_COMPONENTS, BUILTIN_TYPES};
+pub use diagnostic::{Diagnostic, Severity};
+pub use properties::{PROPERTIES, Property};
+pub use reload::{ReloadError, Reloader};
+pub use session::{Change, EditError, Session};
+
+/// Parse a Rice source file into the given DOM, and return the index of its root component.
+/// Enums and components declared in the file can be used by the root component.
+///
+/// All errors are collected before returning them, along with the warnings. Warnings alone do not
+/// make parsing fail (see `Session::diagnostics` to get them all).
+/// Faulty components and properties are skipped, so the DOM may still contain the nodes that were
+/// parsed.
+pub fn parse(content: &str, dom: &mut DOM) -> Result<usize, Vec<Diagnostic>> {
+    let tree = new_parser()
+        .parse(content, None)
+        .expect("Failed to parse content");
+
+    parse_tree(&tree, content, dom)
+}
 
-pub fn parse(content: &str, dom: &mut DOM) -> usize {
+/// Create a tree-sitter parser for the Rice grammar
+fn new_parser() -> tree_sitter::Parser {
     let mut parser = tree_sitter::Parser::new();
     parser
         .set_language(&rice_grammar::LANGUAGE.into())
         .expect("Error loading Rice grammar");
+    parser
+}
 
-    let tree = parser
-        .parse(content, None)
-        .expect("Failed to parse content");
+/// Parse the syntax tree of a Rice source file into the given DOM (see `parse`)
+fn parse_tree(tree: &Tree, content: &str, dom: &mut DOM) -> Result<usize, Vec<Diagnostic>> {
+    match check_tree(tree, content, dom) {
+        (Some(root), diagnostics)
+            if diagnostics
+                .iter()
+                .all(|diagnostic| diagnostic.severity == Severity::Warning) =>
+        {
+            Ok(root)
+        }
+        (_, diagnostics) => Err(diagnostics),
+    }
+}
 
+/// Parse the syntax tree of a Rice source file into the given DOM, and return the index of its
+/// root component if any, with all the errors & warnings
+fn check_tree(tree: &Tree, content: &str, dom: &mut DOM) -> (Option<usize>, Vec<Diagnostic>) {
+    let mut diagnostics = Vec::new();
     let mut root = None;
 
+    syntax_errors(tree.root_node(), content.as_bytes(), &mut diagnostics);
+
+    // 1. Register declarations, so that they can be used before being declared
+    let mut enums = HashMap::new();
+    for node in tree.root_node().children(&mut tree.walk()) {
+        if node.kind() == "enum_decl" {
+            parse_enum_decl(node, content.as_bytes(), &mut enums, &mut diagnostics);
+        }
+    }
+    let mut components = HashMap::new();
+    for node in tree.root_node().children(&mut tree.walk()) {
+        if node.kind() == "component_decl" {
+            let content = content.as_bytes();
+            parse_component_decl(node, content, &enums, &mut components, &mut diagnostics);
+        }
+    }
+    let scope = Scope::root(&components, &enums);
+
+    // 2. Check declaration bodies once, whether they are used or not
+    for node in tree.root_node().children(&mut tree.walk()) {
+        if node.kind() == "component_decl" {
+            validate_component_decl(node, content.as_bytes(), &scope, &mut diagnostics);
+        }
+    }
+
+    // 3. Parse the root component
     for node in tree.root_node().children(&mut tree.walk()) {
         match node.kind() {
             "component" => {
                 if root.is_some() {
-                    panic!("Multiple root components found");
+                    diagnostics.push(Diagnostic::new(node, "Multiple root components found"));
+                    continue;
                 }
-                root = Some(parse_component(node, content.as_bytes(), dom));
+                root = Some(parse_component(
+                    node,
+                    content.as_bytes(),
+                    dom,
+                    &scope,
+                    &mut diagnostics,
+                ));
             }
             _ => {}
         }
     }
 
-    root.expect("No root component found")
+    // 4. Warn about the declarations that the file does not use. Files without a root component
+    // (e.g. component libraries) are not expected to use them.
+    match root {
+        Some(_) => unused_declarations(tree.root_node(), content.as_bytes(), &mut diagnostics),
+        None => diagnostics.push(Diagnostic::new(tree.root_node(), "No root component found")),
+    }
+
+    // Declaration bodies are parsed on their own, then for each use: report their errors once
+    let mut reported = HashSet::new();
+    diagnostics.retain(|diagnostic| reported.insert(diagnostic.clone()));
+
+    (root, diagnostics)
+}
+
+#[cfg(test)]
+mod tests {
+    use rice_dom::{Color, ComputedStyle, GradientKind, ImageFit, Shadow, StyleProp, StyleValue};
+
+    use rice_layout::{Align, AlignSelf, Direction, Gap, Insets, Justify, Size};
+
+    use super::*;
+
+    /// Parse a valid source file into a new DOM, returning it with its root index
+    fn parse_ok(content: &str) -> (DOM, usize) {
+        let mut dom = DOM::new();
+        match parse(content, &mut dom) {
+            Ok(root) => (dom, root),
+            Err(diagnostics) => panic!("Unexpected diagnostics: {:?}", diagnostics),
+        }
+    }
+
+    /// Diagnostics of a faulty source file
+    fn parse_err(content: &str) -> Vec<Diagnostic> {
+        parse(content, &mut DOM::new()).expect_err("Expected diagnostics")
+    }
+
+    /// Computed style of a Rect with the given properties
+    fn style(properties: &str) -> ComputedStyle {
+        let (dom, root) = parse_ok(&format!("Rect {{ {} }}", properties));
+        dom.styles[root].clone()
+    }
+
+    #[test]
+    fn test_diagnostics() {
+        // Located on the faulty node, with 0-based lines & byte columns
+        let diagnostics = parse_err("Rect {\n    colour: #ff0000\n}");
+        assert_eq!(
+            diagnostics,
+            vec![Diagnostic {
+                range: 11..17,
+                line: 1,
+                column: 4,
+                severity: Severity::Error,
+                message: "Unknown property name: colour".to_string(),
+            }]
+        );
+        assert_eq!(
+            diagnostics[0].to_string(),
+            "2:5: Unknown property name: colour"
+        );
+
+        // All errors are collected, in source order
+        let diagnostics = parse_err("Rect { width: red Foo {} height: 1px 2px }");
+        let messages: Vec<_> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "Unknown size identifier: red",
+                "Unknown component class name: Foo",
+                "Expected a single value for property height",
+            ]
+        );
+        assert_eq!(diagnostics[0].range, 14..17);
+        assert_eq!(diagnostics[1].range, 18..21);
+
+        let diagnostics = parse_err("Rect {}\nRect {}");
+        assert_eq!(diagnostics[0].message, "Multiple root components found");
+        assert_eq!(
+            (diagnostics[0].range.clone(), diagnostics[0].line),
+            (8..15, 1)
+        );
+
+        let diagnostics = parse_err("// Nothing to see here");
+        assert_eq!(diagnostics[0].message, "No root component found");
+
+        // Syntax errors are reported along with the other errors
+        let diagnostics = parse_err("Rect { colour: #ff0000 }\n}");
+        assert!(diagnostics[0].message.starts_with("Syntax error"));
+        assert!(
+            diagnostics
+                .iter()
+                .any(|d| d.message == "Unknown property name: colour")
+        );
+    }
+
+    #[test]
+    fn test_layout() {
+        let (dom, root) = parse_ok(
+            "Row {
+                width: 50%
+                height: 2fr
+                gap: auto
+                padding: 4px 8px
+                margin: 1px 2px 3px
+            }",
+        );
+        let layout = &dom.layouts[root];
+        assert_eq!(layout.size, [Size::Percent(0.5), Size::Expand(2.0)]);
+        assert_eq!(layout.direction, Direction::Horizontal(Align::Start));
+        assert_eq!(layout.gap, Gap::Auto);
+        assert_eq!(layout.padding, Insets::new(4, 4, 8, 8));
+        assert_eq!(layout.margin, Insets::new(1, 3, 2, 2));
+
+        let (dom, root) = parse_ok(
+            "Column {
+                width: fit
+                height: 100px
+                min_width: 10px
+                max_height: 80px
+                gap: 8px
+                align: center
+                justify: space_evenly
+                align_self: stretch
+                padding: 1px 2px 3px 4px
+            }",
+        );
+        let layout = &dom.layouts[root];
+        assert_eq!(layout.size, [Size::Fit, Size::Fixed(100)]);
+        assert_eq!(layout.min_size, [Some(10), None]);
+        assert_eq!(layout.max_size, [None, Some(80)]);
+        assert_eq!(layout.gap, Gap::Fixed(8));
+        assert_eq!(layout.direction, Direction::Vertical(Align::Center));
+        assert_eq!(layout.justify, Justify::SpaceEvenly);
+        assert_eq!(layout.align_self, Some(AlignSelf::Stretch));
+        assert_eq!(layout.padding, Insets::new(1, 3, 4, 2));
+
+        let diagnostics = parse_err("Rect { padding: 1px 2px 3px 4px 5px }");
+        assert_eq!(diagnostics[0].message, "Expected 1 to 4 insets, found 5");
+        let diagnostics = parse_err("Rect { width: 1px 2px }");
+        assert_eq!(
+            diagnostics[0].message,
+            "Expected a single value for property width"
+        );
+        let diagnostics = parse_err("Rect { align.hover: center }");
+        assert_eq!(
+            diagnostics[0].message,
+            "Layout property align cannot have a variant"
+        );
+    }
+
+    /// Card with typed properties, some of them optional
+    const CARD: &str = "
+        component Card {
+            /// Inner spacing
+            pad Pixels = 8px
+            color Color
+            Column {
+                padding: pad
+                radius: pad
+                bg_color: color
+                Rect { height: 10px }
+            }
+        }
+    ";
+
+    #[test]
+    fn test_components() {
+        let (dom, root) = parse_ok(&format!(
+            "{} Row {{ Card {{ color: #ff0000 Rect {{}} }} Card {{ pad: 2px color: #00ff00 }} }}",
+            CARD
+        ));
+        let [first, second] = dom.children[root][..] else {
+            panic!("Expected 2 cards");
+        };
+
+        // Bound properties are used as values, with their defaults
+        assert_eq!(dom.layouts[first].padding, Insets::uniform(8));
+        assert_eq!(dom.styles[first].border_radius, [8.0; 4]);
+        assert_eq!(
+            dom.styles[first].background_color,
+            Color::from_hex("#ff0000")
+        );
+        assert_eq!(dom.layouts[second].padding, Insets::uniform(2));
+        assert_eq!(
+            dom.styles[second].background_color,
+            Color::from_hex("#00ff00")
+        );
+
+        // Body roots are named after their declared component
+        assert_eq!(dom.classes[root], "Row");
+        assert_eq!(dom.classes[first], "Card");
+        assert_eq!(dom.classes[dom.children[first][0]], "Rect");
+
+        // Children given at the usage site are added to the body root
+        assert_eq!(dom.children[first].len(), 2);
+        assert_eq!(dom.children[second].len(), 1);
+
+        let messages = |usage: &str| -> Vec<String> {
+            parse_err(&format!("{} {}", CARD, usage))
+                .into_iter()
+                .map(|diagnostic| diagnostic.message)
+                .collect()
+        };
+        // Properties without a value are left unchecked in the body
+        assert_eq!(
+            messages("Card {}"),
+            vec!["Missing property color for component Card"]
+        );
+        assert_eq!(
+            messages("Card { color: 4px }")[0],
+            "Expected Color for property color, found 4px"
+        );
+        assert_eq!(
+            messages("Card { color: #ff0000 colour: #ff0000 }"),
+            vec!["Unknown property colour for component Card"]
+        );
+
+        // Properties cannot be used among multiple values, whatever their position
+        for padding in ["pad 4px", "4px pad"] {
+            let content = format!(
+                "component Box {{ pad Pixels = 8px Rect {{ padding: {} }} }} Box {{}}",
+                padding
+            );
+            let diagnostics = parse_err(&content);
+            assert_eq!(diagnostics.len(), 1);
+            assert_eq!(
+                diagnostics[0].message,
+                "Property pad can only be used as a single value"
+            );
+            let start = content.rfind("pad").unwrap();
+            assert_eq!(diagnostics[0].range, start..start + 3);
+        }
+    }
+
+    #[test]
+    fn test_declaration_diagnostics() {
+        // Errors in declaration bodies are reported once, however many times they are used
+        let diagnostics = parse_err(
+            "component Bad { Rect { colour: #ff0000 } }
+            Column { Bad {} Bad {} }",
+        );
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].message, "Unknown property name: colour");
+
+        // Unused declarations are warnings, which do not make parsing fail
+        let source = "enum Shade { dark }
+            component Unused { Rect {} }
+            Rect {}";
+        parse_ok(source);
+        let diagnostics = Session::new(source.to_string()).diagnostics();
+        let messages: Vec<_> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            vec!["Enum Shade is never used", "Component Unused is never used"]
+        );
+        assert_eq!(diagnostics[0].range, 5..10);
+        assert!(diagnostics.iter().all(|d| d.severity == Severity::Warning));
+        assert_eq!(
+            diagnostics[0].to_string(),
+            "1:6: warning: Enum Shade is never used"
+        );
+
+        // Warnings are returned along with errors
+        let diagnostics = parse_err("component Unused { Rect {} } Rect { colour: #ff0000 }");
+        let messages: Vec<_> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "Unknown property name: colour",
+                "Component Unused is never used"
+            ]
+        );
+
+        // Declarations used by other declarations are used
+        let (dom, root) = parse_ok(
+            "enum Shade { dark light }
+            component Tile { shade Shade = dark Rect { bg_color.dark: #000000 } }
+            component Grid { Row { Tile {} Tile { shade: light } } }
+            Grid {}",
+        );
+        assert_eq!(dom.children[root].len(), 2);
+
+        // Files without a root component are not expected to use their declarations
+        let diagnostics = parse_err("component Unused { Rect {} }");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].message, "No root component found");
+
+        // Declaration bodies are checked even if they are never used, with their properties
+        // bound to their defaults, or left unchecked
+        let diagnostics = parse_err(
+            "component Broken {
+                pad Pixels = 4px
+                size Pixels
+                Image { padding: pad width: size colour: #ff0000 src: size }
+            }
+            Rect {}",
+        );
+        let messages: Vec<_> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "Unknown property name: colour",
+                "Component Broken is never used",
+            ]
+        );
+        let diagnostics = parse_err("component Card { Rect { width: red } }");
+        let messages: Vec<_> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            vec!["Unknown size identifier: red", "No root component found"]
+        );
+
+        let diagnostics = parse_err("component Loop { Loop {} } Loop {}");
+        assert_eq!(diagnostics[0].message, "Component Loop is used recursively");
+    }
+
+    /// Button with colors switched by an enum property
+    const BUTTON: &str = "
+        enum Kind {
+            /// Main action
+            primary
+            secondary
+        }
+
+        component Button {
+            kind Kind = primary
+            Rect {
+                bg_color.primary: #0000ff
+                bg_color.secondary: #808080
+                bg_color.primary.hover: #000080
+            }
+        }
+    ";
+
+    #[test]
+    fn test_enums() {
+        let (dom, root) = parse_ok(&format!(
+            "{} Column {{ Button {{}} Button {{ kind: secondary }} }}",
+            BUTTON
+        ));
+        let [primary, secondary] = dom.children[root][..] else {
+            panic!("Expected 2 buttons");
+        };
+        assert_eq!(
+            dom.styles[primary].background_color,
+            Color::from_hex("#0000ff")
+        );
+        assert_eq!(
+            dom.stylesheets[primary]
+                .hovered
+                .get(&StyleProp::BackgroundColor),
+            Some(&StyleValue::Color(Color::from_hex("#000080")))
+        );
+        assert_eq!(
+            dom.styles[secondary].background_color,
+            Color::from_hex("#808080")
+        );
+        assert!(dom.stylesheets[secondary].hovered.is_empty());
+
+        // Values must be variants of the enum
+        let content = format!("{} Button {{ kind: tertiary }}", BUTTON);
+        let diagnostics = parse_err(&content);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].message,
+            "Expected Kind for property kind, found tertiary"
+        );
+        let start = content.find("tertiary").unwrap();
+        assert_eq!(diagnostics[0].range, start..start + 8);
+
+        // Variants are only known within components with an enum property
+        let diagnostics = parse_err(&format!("{} Rect {{ bg_color.primary: #ffffff }}", BUTTON));
+        assert_eq!(diagnostics[0].message, "Unknown property variant: primary");
+
+        // Properties of disabled variants are checked all the same
+        let diagnostics = parse_err(&format!(
+            "{} component Tag {{
+                kind Kind = primary
+                Rect {{ bg_color.secondary: 4px colour.secondary: #ffffff width.secondary: red }}
+            }}
+            Tag {{}}",
+            BUTTON
+        ));
+        let messages: Vec<_> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "Expected a color, found pixels",
+                "Unknown property name: colour",
+                "Unknown size identifier: red",
+                "Component Button is never used",
+            ]
+        );
+
+        let diagnostics = parse_err("enum Kind { a a } Rect {}");
+        assert_eq!(diagnostics[0].message, "Variant a is already declared");
+        assert_eq!(diagnostics[0].range, 14..15);
+        let diagnostics = parse_err("enum Color { a } Rect {}");
+        assert_eq!(diagnostics[0].message, "Type Color is already defined");
+        let diagnostics = parse_err("component Box { kind Shape = a Rect {} } Box {}");
+        assert_eq!(diagnostics[0].message, "Unknown property type: Shape");
+    }
+
+    #[test]
+    fn test_radius() {
+        // Clockwise from the top left corner, as in CSS
+        for (radius, corners) in [
+            ("4px", [4.0; 4]),
+            ("4px 8px", [4.0, 8.0, 4.0, 8.0]),
+            ("1px 2px 3px", [1.0, 2.0, 3.0, 2.0]),
+            ("1px 2px 3px 4px", [1.0, 2.0, 3.0, 4.0]),
+        ] {
+            let style = style(&format!("radius: {}", radius));
+            assert_eq!(style.border_radius, corners, "{}", radius);
+        }
+
+        let (dom, root) = parse_ok("Rect { radius.hover: 8px }");
+        assert_eq!(
+            dom.stylesheets[root].hovered.get(&StyleProp::BorderRadius),
+            Some(&StyleValue::Corners([8.0; 4]))
+        );
+
+        let diagnostics = parse_err("Rect { radius: 1px 2px 3px 4px 5px }");
+        assert_eq!(
+            diagnostics[0].message,
+            "Expected 1 to 4 corner radii, found 5"
+        );
+    }
+
+    #[test]
+    fn test_border() {
+        // Same order as insets: top, right, bottom, left
+        for (width, sides) in [
+            ("2px", [2.0; 4]),
+            ("1px 2px", [1.0, 2.0, 1.0, 2.0]),
+            ("1px 2px 3px", [1.0, 2.0, 3.0, 2.0]),
+            ("1px 2px 3px 4px", [1.0, 2.0, 3.0, 4.0]),
+        ] {
+            let style = style(&format!("border_width: {} border_color: #ff0000", width));
+            assert_eq!(style.border_width, sides, "{}", width);
+            assert_eq!(style.border_color, Color::from_hex("#ff0000"));
+        }
+
+        let diagnostics = parse_err("Rect { border_width: 1px 2px 3px 4px 5px }");
+        assert_eq!(diagnostics[0].message, "Expected 1 to 4 insets, found 5");
+        let diagnostics = parse_err("Rect { border_color: 2px }");
+        assert_eq!(diagnostics[0].message, "Expected a color, found pixels");
+    }
+
+    #[test]
+    fn test_gradient() {
+        let radial = style("bg_gradient: radial 50% 25% 20px #ff0000 #0000ff");
+        let gradient = radial.background_gradient.unwrap();
+        assert_eq!(
+            gradient.kind,
+            GradientKind::Radial {
+                center: [0.5, 0.25],
+                radius: 20.0,
+            }
+        );
+        let stops: Vec<_> = gradient.stops.iter().map(|stop| stop.position).collect();
+        assert_eq!(stops, vec![0.0, 1.0]);
+        assert_eq!(gradient.stops[0].color, Color::from_hex("#ff0000"));
+
+        // Centered by default
+        let radial = style("bg_gradient: radial 20px #ff0000 #0000ff");
+        assert_eq!(
+            radial.background_gradient.unwrap().kind,
+            GradientKind::Radial {
+                center: [0.5, 0.5],
+                radius: 20.0,
+            }
+        );
+
+        // Stops without positions are spread evenly between the others
+        let linear = style("bg_gradient: linear 90deg #ff0000 #00ff00 #0000ff 80% #ffffff");
+        let gradient = linear.background_gradient.unwrap();
+        assert_eq!(gradient.kind, GradientKind::Linear { angle: 90.0 });
+        let stops: Vec<_> = gradient.stops.iter().map(|stop| stop.position).collect();
+        assert_eq!(stops, vec![0.0, 0.4, 0.8, 1.0]);
+
+        let diagnostics = parse_err("Rect { bg_gradient: radial #ff0000 #0000ff }");
+        assert_eq!(
+            diagnostics[0].message,
+            "Expected a pixel amount, found hex_color"
+        );
+    }
+
+    #[test]
+    fn test_shadow() {
+        let shadow = style("shadow: 0px 2px 8px #00000080").shadow;
+        assert_eq!(
+            shadow,
+            Shadow {
+                offset: [0.0, 2.0],
+                blur: 8.0,
+                spread: 0.0,
+                color: Color::from_hex("#00000080"),
+            }
+        );
+
+        // Offsets & spread can be negative, unlike the blur radius
+        let shadow = style("shadow: -2px -4px 0px -1px #000000").shadow;
+        assert_eq!(shadow.offset, [-2.0, -4.0]);
+        assert_eq!(shadow.spread, -1.0);
+        let diagnostics = parse_err("Rect { shadow: 0px 2px -8px #000000 }");
+        assert_eq!(
+            diagnostics[0].message,
+            "Shadow blur radius cannot be negative"
+        );
+
+        let diagnostics = parse_err("Rect { shadow: 2px #000000 }");
+        assert_eq!(
+            diagnostics[0].message,
+            "Expected 2 to 4 shadow amounts, found 1"
+        );
+    }
+
+    #[test]
+    fn test_negative_lengths() {
+        for property in [
+            "width: -10px",
+            "min_height: -1px",
+            "padding: 4px -4px",
+            "gap: -2px",
+            "radius: -2px",
+            "border_width: 1px -1px",
+            "bg_gradient: radial -20px #ff0000 #0000ff",
+        ] {
+            let diagnostics = parse_err(&format!("Rect {{ {} }}", property));
+            assert_eq!(diagnostics.len(), 1, "{}", property);
+            assert!(
+                diagnostics[0]
+                    .message
+                    .starts_with("Pixel amount cannot be negative"),
+                "{}: {}",
+                property,
+                diagnostics[0].message
+            );
+        }
+    }
+
+    #[test]
+    fn test_image() {
+        let (dom, root) = parse_ok(
+            r#"Image {
+                src: "icon.png"
+                fit: contain
+            }"#,
+        );
+        let style = &dom.styles[root];
+        assert_eq!(style.image.as_deref(), Some("icon.png"));
+        assert_eq!(style.image_fit, ImageFit::Contain);
+
+        // Images fill their element by default
+        let (dom, root) = parse_ok(r#"Image { src: "icon.png" }"#);
+        assert_eq!(dom.styles[root].image_fit, ImageFit::Fill);
+        let (dom, root) = parse_ok(r#"Image { src: "icon.png" fit: cover tint: #ff000080 }"#);
+        assert_eq!(dom.styles[root].image_fit, ImageFit::Cover);
+        assert_eq!(
+            dom.styles[root].image_tint,
+            Some(Color::from_hex("#ff000080"))
+        );
+
+        let diagnostics = parse_err(r#"Image { src: "icon.png" fit: crop }"#);
+        assert_eq!(
+            diagnostics[0].message,
+            "Expected fill, contain or cover, found crop"
+        );
+
+        // Images need a source, reported on their class name
+        let diagnostics = parse_err("Image { fit: cover }");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].message,
+            "Image component without a src property"
+        );
+        assert_eq!(diagnostics[0].range, 0..5);
+
+        // Sources of states only are not enough, but can override the default source
+        let diagnostics = parse_err(r#"Image { src.hover: "icon.png" }"#);
+        assert_eq!(diagnostics.len(), 1);
+
+        let (dom, root) = parse_ok(r#"Image { src: "a.png" src.hover: "b.png" }"#);
+        assert_eq!(
+            dom.stylesheets[root].hovered.get(&StyleProp::Image),
+            Some(&StyleValue::Image("b.png".to_string()))
+        );
+    }
 }
@@ -3,65 +3,370 @@
 use core::str;
 
 use rice_dom::{StyleProp, StyleSheet, StyleValue};
-use rice_layout::Layout;
+use rice_layout::{Direction, Layout};
 use tree_sitter::Node;
 
-use crate::values::{parse_color, parse_size};
+use crate::{
+    Diagnostic,
+    declarations::Scope,
+    values::{
+        parse_align, parse_align_self, parse_color, parse_corners, parse_fit, parse_gap,
+        parse_gradient, parse_insets, parse_justify, parse_length, parse_shadow, parse_sides,
+        parse_size, parse_string,
+    },
+};
+
+/// Built-in property: its name, the identifiers its values accept, and how it is applied
+pub struct Property {
+    pub name: &'static str,
+    pub identifiers: &'static [&'static str],
+    apply: Apply,
+}
+
+/// How a property applies its values
+enum Apply {
+    /// Style property, which can have hover & click variants
+    Style(StyleProp, fn(&Values) -> Result<StyleValue, Diagnostic>),
+    /// Layout property, set in the layout of the component
+    Layout(fn(&Values, &mut Layout) -> Result<(), Diagnostic>),
+}
+
+/// Values given to a property, with the property node for diagnostics
+struct Values<'n, 'a> {
+    node: Node<'a>,
+    name: &'n str,
+    values: &'n [Node<'a>],
+    content: &'n [u8],
+}
+
+impl<'a> Values<'_, 'a> {
+    /// Single value, for properties that do not take multiple values
+    fn single(&self) -> Result<Node<'a>, Diagnostic> {
+        match self.values {
+            [value] => Ok(*value),
+            [] => Err(Diagnostic::new(
+                self.node,
+                format!("Expected a value for property {}", self.name),
+            )),
+            _ => Err(Diagnostic::new(
+                self.node,
+                format!("Expected a single value for property {}", self.name),
+            )),
+        }
+    }
+}
+
+/// Built-in properties
+pub const PROPERTIES: &[Property] = &[
+    // Layout
+    Property {
+        name: "width",
+        identifiers: &["fit"],
+        apply: Apply::Layout(|v, layout| {
+            layout.size[0] = parse_size(v.single()?, v.content)?;
+            Ok(())
+        }),
+    },
+    Property {
+        name: "height",
+        identifiers: &["fit"],
+        apply: Apply::Layout(|v, layout| {
+            layout.size[1] = parse_size(v.single()?, v.content)?;
+            Ok(())
+        }),
+    },
+    Property {
+        name: "min_width",
+        identifiers: &[],
+        apply: Apply::Layout(|v, layout| {
+            layout.min_size[0] = Some(parse_length(v.single()?, v.content)?);
+            Ok(())
+        }),
+    },
+    Property {
+        name: "min_height",
+        identifiers: &[],
+        apply: Apply::Layout(|v, layout| {
+            layout.min_size[1] = Some(parse_length(v.single()?, v.content)?);
+            Ok(())
+        }),
+    },
+    Property {
+        name: "max_width",
+        identifiers: &[],
+        apply: Apply::Layout(|v, layout| {
+            layout.max_size[0] = Some(parse_length(v.single()?, v.content)?);
+            Ok(())
+        }),
+    },
+    Property {
+        name: "max_height",
+        identifiers: &[],
+        apply: Apply::Layout(|v, layout| {
+            layout.max_size[1] = Some(parse_length(v.single()?, v.content)?);
+            Ok(())
+        }),
+    },
+    Property {
+        name: "margin",
+        identifiers: &[],
+        apply: Apply::Layout(|v, layout| {
+            layout.margin = parse_insets(v.node, v.values, v.content)?;
+            Ok(())
+        }),
+    },
+    Property {
+        name: "padding",
+        identifiers: &[],
+        apply: Apply::Layout(|v, layout| {
+            layout.padding = parse_insets(v.node, v.values, v.content)?;
+            Ok(())
+        }),
+    },
+    Property {
+        name: "gap",
+        identifiers: &["auto"],
+        apply: Apply::Layout(|v, layout| {
+            layout.gap = parse_gap(v.single()?, v.content)?;
+            Ok(())
+        }),
+    },
+    Property {
+        name: "align",
+        identifiers: &["start", "center", "end"],
+        apply: Apply::Layout(|v, layout| {
+            let align = parse_align(v.single()?, v.content)?;
+            layout.direction = match layout.direction {
+                Direction::Vertical(_) => Direction::Vertical(align),
+                Direction::Horizontal(_) => Direction::Horizontal(align),
+                Direction::Grid(_) => Direction::Grid(align),
+            };
+            Ok(())
+        }),
+    },
+    Property {
+        name: "justify",
+        identifiers: &["start", "center", "end", "space_around", "space_evenly"],
+        apply: Apply::Layout(|v, layout| {
+            layout.justify = parse_justify(v.single()?, v.content)?;
+            Ok(())
+        }),
+    },
+    Property {
+        name: "align_self",
+        identifiers: &["start", "center", "end", "stretch"],
+        apply: Apply::Layout(|v, layout| {
+            layout.align_self = Some(parse_align_self(v.single()?, v.content)?);
+            Ok(())
+        }),
+    },
+    // Style
+    Property {
+        name: "bg_color",
+        identifiers: &[],
+        apply: Apply::Style(StyleProp::BackgroundColor, |v| {
+            Ok(StyleValue::Color(parse_color(v.single()?, v.content)?))
+        }),
+    },
+    Property {
+        name: "bg_gradient",
+        identifiers: &["linear", "radial"],
+        apply: Apply::Style(StyleProp::BackgroundGradient, |v| {
+            Ok(StyleValue::Gradient(parse_gradient(
+                v.node, v.values, v.content,
+            )?))
+        }),
+    },
+    Property {
+        name: "radius",
+        identifiers: &[],
+        apply: Apply::Style(StyleProp::BorderRadius, |v| {
+            Ok(StyleValue::Corners(parse_corners(
+                v.node, v.values, v.content,
+            )?))
+        }),
+    },
+    Property {
+        name: "border_width",
+        identifiers: &[],
+        apply: Apply::Style(StyleProp::BorderWidth, |v| {
+            Ok(StyleValue::Sides(parse_sides(v.node, v.values, v.content)?))
+        }),
+    },
+    Property {
+        name: "border_color",
+        identifiers: &[],
+        apply: Apply::Style(StyleProp::BorderColor, |v| {
+            Ok(StyleValue::Color(parse_color(v.single()?, v.content)?))
+        }),
+    },
+    Property {
+        name: "shadow",
+        identifiers: &[],
+        apply: Apply::Style(StyleProp::BoxShadow, |v| {
+            Ok(StyleValue::Shadow(parse_shadow(
+                v.node, v.values, v.content,
+            )?))
+        }),
+    },
+    Property {
+        name: "src",
+        identifiers: &[],
+        apply: Apply::Style(StyleProp::Image, |v| {
+            Ok(StyleValue::Image(parse_string(v.single()?, v.content)?))
+        }),
+    },
+    Property {
+        name: "fit",
+        identifiers: &["fill", "contain", "cover"],
+        apply: Apply::Style(StyleProp::ImageFit, |v| {
+            Ok(StyleValue::Fit(parse_fit(v.single()?, v.content)?))
+        }),
+    },
+    Property {
+        name: "tint",
+        identifiers: &[],
+        apply: Apply::Style(StyleProp::ImageTint, |v| {
+            Ok(StyleValue::Color(parse_color(v.single()?, v.content)?))
+        }),
+    },
+];
 
 /// Parse a property node and update the given layout and stylesheet accordingly.
-pub fn parse_property(
-    node: Node,
+/// Faulty properties are skipped, and reported in the diagnostics.
+pub fn parse_property<'a>(
+    node: Node<'a>,
     content: &[u8],
     layout: &mut Layout,
     stylesheet: &mut StyleSheet,
+    scope: &Scope<'_, 'a>,
+    diagnostics: &mut Vec<Diagnostic>,
 ) {
-    // Populate propname & value
-    let mut propname = "";
+    if let Err(diagnostic) = try_parse_property(node, content, layout, stylesheet, scope) {
+        diagnostics.push(diagnostic);
+    }
+}
+
+/// Error for a property of the instantiated component used among multiple values
+fn single_value(node: Node, name: &str) -> Diagnostic {
+    Diagnostic::new(
+        node,
+        format!("Property {} can only be used as a single value", name),
+    )
+}
+
+/// Parse a property node, stopping at the first error
+fn try_parse_property<'a>(
+    node: Node<'a>,
+    content: &[u8],
+    layout: &mut Layout,
+    stylesheet: &mut StyleSheet,
+    scope: &Scope<'_, 'a>,
+) -> Result<(), Diagnostic> {
+    // Populate propname & values
+    let mut propname = None;
     let mut variant = None; // Hover or clicked
-    let mut value = None;
+    let mut enabled = true; // Whether enum variant conditions hold
+    let mut values = Vec::new();
 
     for child in node.named_children(&mut node.walk()) {
         match child.kind() {
             "propname" => {
-                propname = str::from_utf8(&content[child.byte_range()]).unwrap();
+                propname = Some(child);
             }
             "identifier" => {
                 let id_str = str::from_utf8(&content[child.byte_range()]).unwrap();
-                match id_str {
-                    "hover" => variant = Some("hovered"),
-                    "click" => variant = Some("clicked"),
-                    _ => panic!("Unknown identifier: {}", id_str),
+                match (id_str, scope.condition(id_str, content)) {
+                    ("hover", _) => variant = Some("hovered"),
+                    ("click", _) => variant = Some("clicked"),
+                    // Variant of an enum property of the instantiated component
+                    (_, Some(condition)) => enabled &= condition,
+                    _ => {
+                        return Err(Diagnostic::new(
+                            child,
+                            format!("Unknown property variant: {}", id_str),
+                        ));
+                    }
                 }
             }
-            "value" => {
-                value = Some(child.named_child(0).expect("Expected value child node"));
+            // Identifiers naming bound properties are replaced with their values
+            "value" => values.extend(
+                child
+                    .named_child(0)
+                    .map(|value| scope.resolve(value, content)),
+            ),
+            // Multiple values cannot refer to properties (see the grammar)
+            "values" => {
+                for value in child
+                    .named_children(&mut child.walk())
+                    .filter_map(|value| value.named_child(0))
+                {
+                    let text = str::from_utf8(&content[value.byte_range()]).unwrap();
+                    if value.kind() == "identifier" && scope.declares(text) {
+                        return Err(single_value(value, text));
+                    }
+                    values.push(value);
+                }
             }
-            _ => unreachable!("Unexpected node kind for property: {}", child.kind()),
+
+            // Syntax errors are reported separately
+            _ => {}
         }
     }
 
-    // Match possibilities
-    match propname.as_ref() {
-        "width" => {
-            layout.size[0] = parse_size(value.expect("Expected value node"), content);
-        }
-        "height" => {
-            layout.size[1] = parse_size(value.expect("Expected value node"), content);
+    let Some(propname) = propname else {
+        return Err(Diagnostic::new(node, "Expected a property name"));
+    };
+    let name = str::from_utf8(&content[propname.byte_range()]).unwrap();
+
+    // A property given after another value is parsed as a property without a value
+    // (e.g. "padding: 4px pad")
+    if values.is_empty() && scope.declares(name) {
+        return Err(single_value(propname, name));
+    }
+
+    let Some(property) = PROPERTIES.iter().find(|property| property.name == name) else {
+        return Err(Diagnostic::new(
+            propname,
+            format!("Unknown property name: {}", name),
+        ));
+    };
+    // Properties of the instantiated component without a value cannot be checked
+    if values.iter().any(|value| scope.unbound(*value, content)) {
+        return Ok(());
+    }
+    let values = Values {
+        node,
+        name,
+        values: &values,
+        content,
+    };
+
+    // Properties of disabled enum variants are checked, but not applied
+    match (&property.apply, variant) {
+        (Apply::Style(_, parse), _) if !enabled => {
+            parse(&values)?;
         }
-        "bg_color" => {
+        (Apply::Style(prop, parse), variant) => {
+            let style = parse(&values)?;
             match variant {
                 Some("hovered") => &mut stylesheet.hovered,
                 Some("clicked") => &mut stylesheet.clicked,
                 None => &mut stylesheet.default,
                 _ => unreachable!("Unexpected variant: {:?}", variant),
             }
-            .insert(
-                StyleProp::BackgroundColor,
-                StyleValue::Color(parse_color(value.expect("Expected value node"), content)),
-            );
+            .insert(*prop, style);
         }
-        _ => {
-            panic!("Unknown property name: {}", propname);
+        // Layout properties do not have hover / click variants
+        (Apply::Layout(_), Some(_)) => {
+            return Err(Diagnostic::new(
+                node,
+                format!("Layout property {} cannot have a variant", name),
+            ));
         }
+        (Apply::Layout(parse), None) if !enabled => parse(&values, &mut layout.clone())?,
+        (Apply::Layout(parse), None) => parse(&values, layout)?,
     }
+
+    Ok(())
 }
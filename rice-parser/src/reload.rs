@@ -0,0 +1,285 @@
+//! Hot reload of Rice source files
+
+use std::{
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use rice_dom::{DOM, StyleProp, StyleValue};
+use rice_layout::Measure;
+
+use crate::{Diagnostic, parse};
+
+/// Error while (re)loading a Rice source file
+#[derive(Debug)]
+pub enum ReloadError {
+    /// The file could not be read
+    Io(io::Error),
+    /// The file has errors, with all their diagnostics
+    Parse(Vec<Diagnostic>),
+}
+
+impl fmt::Display for ReloadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReloadError::Io(error) => write!(f, "{}", error),
+            ReloadError::Parse(diagnostics) => {
+                for (i, diagnostic) in diagnostics.iter().enumerate() {
+                    if i > 0 {
+                        writeln!(f)?;
+                    }
+                    write!(f, "{}", diagnostic)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ReloadError {}
+
+impl From<io::Error> for ReloadError {
+    fn from(error: io::Error) -> Self {
+        ReloadError::Io(error)
+    }
+}
+
+/// Polling-based watcher that reloads a Rice source file into a DOM when it changes.
+///
+/// `poll` only checks the modification time of the file, so it can be called often,
+/// e.g. from the update callback of `rice_wgpu::run_with_updates`.
+/// Relative image sources are resolved against the directory of the file.
+#[derive(Debug, Clone)]
+pub struct Reloader {
+    path: PathBuf,
+    /// Modification time of the last loaded version of the file
+    modified: Option<SystemTime>,
+}
+
+impl Reloader {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            modified: None,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Load the file into a new DOM, and compute its layout
+    pub fn load(&mut self, measure: &dyn Measure) -> Result<DOM, ReloadError> {
+        let mut dom = self.read()?;
+        dom.compute_layout(dom.root, measure);
+        Ok(dom)
+    }
+
+    /// Reload the file into the DOM if it changed since it was last loaded.
+    ///
+    /// Returns none if the file did not change. On errors, the DOM is left untouched, and the
+    /// file is reloaded once it changes again. On success, the DOM is replaced, its root marked
+    /// dirty, and the hover, click & scroll states are kept for nodes at the same place in the tree
+    /// with the same class name & layout.
+    /// Images are loaded again from their files, as they may have changed as well.
+    pub fn poll(
+        &mut self,
+        dom: &mut DOM,
+        measure: &dyn Measure,
+    ) -> Option<Result<(), ReloadError>> {
+        let modified = match fs::metadata(&self.path).and_then(|meta| meta.modified()) {
+            Ok(modified) => Some(modified),
+            Err(error) => return Some(Err(error.into())),
+        };
+        if modified == self.modified {
+            return None;
+        }
+
+        let mut new = match self.read() {
+            Ok(new) => new,
+            Err(error) => return Some(Err(error)),
+        };
+
+        // Scroll offsets are applied (and clamped) by the layout
+        let root = new.root;
+        transfer_state(dom, dom.root, &mut new, root);
+        new.compute_layout(root, measure);
+        new.handle_mouse_moved(dom.mouse);
+        new.dirty.push(new.root);
+        new.reload_images = true;
+        *dom = new;
+
+        Some(Ok(()))
+    }
+
+    /// Parse the file into a new DOM, without computing its layout
+    fn read(&mut self) -> Result<DOM, ReloadError> {
+        self.modified = fs::metadata(&self.path)?.modified().ok();
+
+        let content = fs::read_to_string(&self.path)?;
+        let mut dom = DOM::new();
+        dom.root = parse(&content, &mut dom).map_err(ReloadError::Parse)?;
+        if let Some(dir) = self.path.parent() {
+            resolve_images(&mut dom, dir);
+        }
+
+        Ok(dom)
+    }
+}
+
+/// Resolve the relative image sources of the nodes of a DOM against the given directory
+fn resolve_images(dom: &mut DOM, dir: &Path) {
+    let resolve = |source: &mut String| {
+        if Path::new(source.as_str()).is_relative() {
+            *source = dir.join(source.as_str()).to_string_lossy().into_owned();
+        }
+    };
+
+    for stylesheet in &mut dom.stylesheets {
+        for styles in [
+            &mut stylesheet.default,
+            &mut stylesheet.hovered,
+            &mut stylesheet.clicked,
+        ] {
+            if let Some(StyleValue::Image(source)) = styles.get_mut(&StyleProp::Image) {
+                resolve(source);
+            }
+        }
+    }
+    for style in &mut dom.styles {
+        if let Some(source) = &mut style.image {
+            resolve(source);
+        }
+    }
+}
+
+/// Recursively transfer the click & scroll states of the nodes of a previous DOM to the nodes
+/// at the same place in the tree of a new DOM, as long as both trees match: nodes with another
+/// class name or layout, and their subtrees, start from a blank state.
+fn transfer_state(old: &DOM, old_index: usize, new: &mut DOM, new_index: usize) {
+    if old.classes[old_index] != new.classes[new_index]
+        || old.layouts[old_index] != new.layouts[new_index]
+    {
+        return;
+    }
+
+    if old.clicked == Some(old_index) {
+        new.stylesheets[new_index].apply_clicked(&mut new.styles[new_index]);
+        new.clicked = Some(new_index);
+    }
+    new.rects[new_index].scroll = old.rects[old_index].scroll;
+
+    for i in 0..old.children[old_index]
+        .len()
+        .min(new.children[new_index].len())
+    {
+        let (old_child, new_child) = (old.children[old_index][i], new.children[new_index][i]);
+        transfer_state(old, old_child, new, new_child);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rice_dom::StyleSheet;
+    use rice_layout::{Insets, Layout};
+
+    use super::*;
+
+    /// Column of the given children, by class name & layout
+    fn column(children: &[(&str, Layout)]) -> DOM {
+        let mut dom = DOM::new();
+        let children = children
+            .iter()
+            .map(|(class, layout)| {
+                let index = dom.insert(layout.clone(), StyleSheet::default());
+                dom.classes[index] = class.to_string();
+                index
+            })
+            .collect();
+        dom.root = dom.insert_with_children(Layout::default(), StyleSheet::default(), children);
+        dom.classes[dom.root] = "Column".to_string();
+        dom
+    }
+
+    #[test]
+    fn test_transfer_state() {
+        let list = Layout::default().scroll(false, true);
+        let mut old = column(&[("Column", list.clone()), ("Rect", Layout::default())]);
+        let [scrolled, clicked] = old.children[old.root][..] else {
+            unreachable!();
+        };
+        old.rects[scrolled].scroll = [0, 20];
+        old.clicked = Some(clicked);
+
+        // Matching trees keep their state
+        let mut new = column(&[("Column", list.clone()), ("Rect", Layout::default())]);
+        let root = new.root;
+        transfer_state(&old, old.root, &mut new, root);
+        let children = &new.children[root];
+        assert_eq!(new.rects[children[0]].scroll, [0, 20]);
+        assert_eq!(new.clicked, Some(children[1]));
+
+        // Nodes that moved (e.g. after inserting a sibling) start from a blank state
+        let mut new = column(&[
+            ("Rect", Layout::default()),
+            ("Column", list.clone()),
+            ("Rect", Layout::default()),
+        ]);
+        let root = new.root;
+        transfer_state(&old, old.root, &mut new, root);
+        assert!(new.rects.iter().all(|rect| rect.scroll == [0, 0]));
+        assert_eq!(new.clicked, None);
+
+        // As do nodes whose class name or layout changed
+        let mut new = column(&[("Row", list.clone()), ("Image", Layout::default())]);
+        let root = new.root;
+        transfer_state(&old, old.root, &mut new, root);
+        assert!(new.rects.iter().all(|rect| rect.scroll == [0, 0]));
+        assert_eq!(new.clicked, None);
+
+        let mut new = column(&[
+            ("Column", list.scroll(true, true)),
+            ("Rect", Layout::default().padding(Insets::uniform(4))),
+        ]);
+        let root = new.root;
+        transfer_state(&old, old.root, &mut new, root);
+        assert!(new.rects.iter().all(|rect| rect.scroll == [0, 0]));
+        assert_eq!(new.clicked, None);
+    }
+
+    #[test]
+    fn test_resolve_images() {
+        let mut stylesheet = StyleSheet::default();
+        stylesheet.default.insert(
+            StyleProp::Image,
+            StyleValue::Image("icons/default.png".into()),
+        );
+        let absolute = if cfg!(windows) {
+            "C:\\hovered.png"
+        } else {
+            "/hovered.png"
+        };
+        stylesheet
+            .hovered
+            .insert(StyleProp::Image, StyleValue::Image(absolute.into()));
+
+        let mut dom = DOM::new();
+        let index = dom.insert(Layout::default(), stylesheet);
+        resolve_images(&mut dom, Path::new("ui"));
+
+        // Relative sources are resolved in all states, absolute sources are kept
+        let resolved = Path::new("ui").join("icons/default.png");
+        let resolved = resolved.to_str().unwrap();
+        let stylesheet = &dom.stylesheets[index];
+        assert_eq!(
+            stylesheet.default.get(&StyleProp::Image),
+            Some(&StyleValue::Image(resolved.into()))
+        );
+        assert_eq!(
+            stylesheet.hovered.get(&StyleProp::Image),
+            Some(&StyleValue::Image(absolute.into()))
+        );
+        assert_eq!(dom.styles[index].image.as_deref(), Some(resolved));
+    }
+}
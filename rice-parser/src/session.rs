@@ -0,0 +1,302 @@
+//! Persistent parser session, reparsing incrementally after edits
+
+use std::{fmt, ops::Range};
+
+use rice_dom::DOM;
+use tree_sitter::{InputEdit, Node, Point, Tree};
+
+use crate::{Diagnostic, check_tree, new_parser, parse_tree};
+
+/// Part of a source file changed by an edit
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    /// Component of the root component tree, by positions among the children components
+    /// of each of its ancestors in the source (empty for the root component itself).
+    /// This is a source path: it does not account for the body of declared components, whose
+    /// instances have their own children in the DOM before those given at the usage site.
+    Component(Vec<usize>),
+    /// Declared enum or component, by class name. Its uses changed as well.
+    Declaration(String),
+    /// Anything else at the top level of the file (root component added or removed, ...)
+    Source,
+}
+
+/// Error for an edit whose byte range is reversed, out of the content, or not on character
+/// boundaries (e.g. computed from a stale version of the content)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EditError {
+    pub range: Range<usize>,
+}
+
+impl fmt::Display for EditError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid edit range: {:?}", self.range)
+    }
+}
+
+impl std::error::Error for EditError {}
+
+/// Parser session for a source file, keeping its previous syntax tree to reparse it
+/// incrementally after each edit
+pub struct Session {
+    parser: tree_sitter::Parser,
+    content: String,
+    tree: Tree,
+}
+
+impl Session {
+    pub fn new(content: String) -> Self {
+        let mut parser = new_parser();
+        let tree = parser
+            .parse(&content, None)
+            .expect("Failed to parse content");
+
+        Self {
+            parser,
+            content,
+            tree,
+        }
+    }
+
+    pub fn content(&self) -> &str {
+        &self.content
+    }
+
+    pub fn tree(&self) -> &Tree {
+        &self.tree
+    }
+
+    /// Parse the current content into the given DOM, as with `parse`
+    pub fn parse(&self, dom: &mut DOM) -> Result<usize, Vec<Diagnostic>> {
+        parse_tree(&self.tree, &self.content, dom)
+    }
+
+    /// All the errors & warnings of the current content, including the warnings that do not
+    /// make `parse` fail
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        check_tree(&self.tree, &self.content, &mut DOM::new()).1
+    }
+
+    /// Replace a byte range of the content with the given text, reparse it incrementally,
+    /// and return the outermost parts of the file that changed.
+    /// Invalid ranges are rejected, leaving the content untouched.
+    pub fn edit(&mut self, range: Range<usize>, text: &str) -> Result<Vec<Change>, EditError> {
+        if range.start > range.end
+            || !self.content.is_char_boundary(range.start)
+            || !self.content.is_char_boundary(range.end)
+        {
+            return Err(EditError { range });
+        }
+
+        let start_position = point(&self.content, range.start);
+        let old_end_position = point(&self.content, range.end);
+
+        self.content.replace_range(range.clone(), text);
+        let new_end = range.start + text.len();
+
+        self.tree.edit(&InputEdit {
+            start_byte: range.start,
+            old_end_byte: range.end,
+            new_end_byte: new_end,
+            start_position,
+            old_end_position,
+            new_end_position: point(&self.content, new_end),
+        });
+
+        let tree = self
+            .parser
+            .parse(&self.content, Some(&self.tree))
+            .expect("Failed to parse content");
+
+        // Ranges whose structure changed, and the edited text itself (for changed values)
+        let mut ranges: Vec<_> = self
+            .tree
+            .changed_ranges(&tree)
+            .map(|changed| changed.start_byte..changed.end_byte)
+            .collect();
+        ranges.push(range.start..new_end);
+        self.tree = tree;
+
+        // Outermost changed nodes only, as changes of a node include its descendants
+        let root = self.tree.root_node();
+        let mut nodes: Vec<Node> = Vec::new();
+        for range in ranges {
+            let node = changed_node(root, range);
+            if nodes.iter().any(|&other| contains(other, node)) {
+                continue;
+            }
+            nodes.retain(|&other| !contains(node, other));
+            nodes.push(node);
+        }
+
+        Ok(nodes.into_iter().map(|node| self.change(node)).collect())
+    }
+
+    /// Describe a changed component, declaration or top-level node
+    fn change(&self, node: Node) -> Change {
+        match node.kind() {
+            "component" => Change::Component(component_path(node)),
+            "enum_decl" | "component_decl" => node
+                .named_children(&mut node.walk())
+                .find(|child| child.kind() == "classname")
+                .map(|name| Change::Declaration(self.content[name.byte_range()].to_string()))
+                .unwrap_or(Change::Source),
+            _ => Change::Source,
+        }
+    }
+}
+
+/// Innermost component of the root tree containing a byte range, or else its top-level node
+/// (declaration, or the source file itself)
+fn changed_node(root: Node, range: Range<usize>) -> Node {
+    let mut node = root
+        .descendant_for_byte_range(range.start, range.end)
+        .unwrap_or(root);
+
+    loop {
+        match (node.kind(), node.parent()) {
+            // Components within declarations are part of the declaration
+            ("component", Some(parent)) if !in_declaration(parent) => return node,
+            (_, Some(parent)) if parent.id() == root.id() => return node,
+            (_, Some(parent)) => node = parent,
+            (_, None) => return node,
+        }
+    }
+}
+
+/// Whether a node is a declaration or is within one
+fn in_declaration(node: Node) -> bool {
+    let mut current = Some(node);
+    while let Some(node) = current {
+        if matches!(node.kind(), "enum_decl" | "component_decl") {
+            return true;
+        }
+        current = node.parent();
+    }
+    false
+}
+
+/// Whether a node contains another node
+fn contains(node: Node, other: Node) -> bool {
+    node.start_byte() <= other.start_byte() && other.end_byte() <= node.end_byte()
+}
+
+/// Positions of a component among the children components of each of its ancestors in the
+/// source (see `Change::Component`)
+fn component_path(node: Node) -> Vec<usize> {
+    let mut path = Vec::new();
+    let mut current = node;
+
+    while let Some(parent) = current.parent()
+        && parent.kind() == "component"
+    {
+        let position = parent
+            .named_children(&mut parent.walk())
+            .filter(|child| child.kind() == "component")
+            .position(|child| child.id() == current.id())
+            .unwrap_or(0);
+        path.push(position);
+        current = parent;
+    }
+
+    path.reverse();
+    path
+}
+
+/// Row & byte column of a byte offset in a text
+fn point(text: &str, offset: usize) -> Point {
+    let before = &text.as_bytes()[..offset];
+    let row = before.iter().filter(|&&b| b == b'\n').count();
+    let column = match before.iter().rposition(|&b| b == b'\n') {
+        Some(newline) => offset - newline - 1,
+        None => offset,
+    };
+
+    Point { row, column }
+}
+
+#[cfg(test)]
+mod tests {
+    use rice_layout::Size;
+
+    use super::*;
+
+    const SOURCE: &str = "enum Kind { a b }
+
+component Button {
+    kind Kind = a
+    Rect {}
+}
+
+Column {
+    Rect { width: 10px }
+    Row {
+        Button {}
+        Rect {}
+    }
+}
+";
+
+    /// Byte range of the last occurrence of a pattern in the source
+    fn find(pattern: &str) -> Range<usize> {
+        let start = SOURCE.rfind(pattern).unwrap();
+        start..start + pattern.len()
+    }
+
+    #[test]
+    fn test_changes() {
+        let mut session = Session::new(SOURCE.to_string());
+
+        // Components are located by their path from the root component
+        let changes = session.edit(find("10px"), "20px").unwrap();
+        assert_eq!(changes, vec![Change::Component(vec![0])]);
+        let mut dom = DOM::new();
+        let root = session.parse(&mut dom).unwrap();
+        assert_eq!(dom.layouts[dom.children[root][0]].size[0], Size::Fixed(20));
+
+        // Components within other components, after the declarations
+        let inner = find("Rect {}");
+        let changes = session
+            .edit(inner.end - 1..inner.end - 1, " height: 1px ")
+            .unwrap();
+        assert_eq!(changes, vec![Change::Component(vec![1, 1])]);
+
+        let changes = session.edit(find("a b"), "a b c").unwrap();
+        assert_eq!(changes, vec![Change::Declaration("Kind".to_string())]);
+
+        // The content follows the edits
+        assert!(session.content().contains("Rect { height: 1px }"));
+        assert!(session.content().starts_with("enum Kind { a b c }"));
+    }
+
+    #[test]
+    fn test_source_paths() {
+        let content = "component Card { Column { Rect { height: 10px } } }
+Card { Rect { width: 10px } }";
+        let mut session = Session::new(content.to_string());
+
+        // Paths count the components of the source, not the children of instances in the DOM
+        let start = content.rfind("10px").unwrap();
+        let changes = session.edit(start..start + 4, "20px").unwrap();
+        assert_eq!(changes, vec![Change::Component(vec![0])]);
+
+        let mut dom = DOM::new();
+        let root = session.parse(&mut dom).unwrap();
+        let rect = dom.children[root][1];
+        assert_eq!(dom.layouts[rect].size[0], Size::Fixed(20));
+        assert_eq!(dom.classes[dom.children[root][0]], "Rect");
+    }
+
+    #[test]
+    fn test_invalid_edits() {
+        let content = "// café\nRect {}";
+        let mut session = Session::new(content.to_string());
+
+        // Reversed, out of the content, or within a character
+        for range in [Range { start: 4, end: 2 }, 0..100, 100..100, 7..7] {
+            assert_eq!(session.edit(range.clone(), "x"), Err(EditError { range }));
+        }
+        assert_eq!(session.content(), content);
+        assert!(session.parse(&mut DOM::new()).is_ok());
+    }
+}
@@ -2,39 +2,387 @@
 
 use core::str;
 
-use rice_dom::Color;
-use rice_layout::Size;
+use rice_dom::{Color, ColorStop, Gradient, GradientKind, ImageFit, Shadow};
+use rice_layout::{Align, AlignSelf, Gap, Insets, Justify, Size};
 use tree_sitter::Node;
 
-// TODO: propagate error instead of panicking
+use crate::Diagnostic;
 
 /// Parse a layout size value from a tree-sitter node.
-pub fn parse_size(node: Node, content: &[u8]) -> Size {
+pub fn parse_size(node: Node, content: &[u8]) -> Result<Size, Diagnostic> {
     let text = str::from_utf8(&content[node.byte_range()]).unwrap();
+    let invalid = || Diagnostic::new(node, format!("Invalid size: {}", text));
 
     match node.kind() {
-        "pixels" => Size::Fixed(text[..text.len() - 2].parse().unwrap()),
-        "fraction" => Size::Expand(text[..text.len() - 2].parse().unwrap()),
-        "percentage" => Size::Percent(text[..text.len() - 1].parse().unwrap()),
+        "pixels" => Ok(Size::Fixed(parse_length(node, content)?)),
+        "fraction" => Ok(Size::Expand(
+            text[..text.len() - 2].parse().map_err(|_| invalid())?,
+        )),
+        "percentage" => Ok(Size::Percent(parse_percentage(node, content)?)),
         "identifier" => match text {
-            "fit" => Size::Fit,
-            _ => panic!("Unknown size identifier: {}", text),
+            "fit" => Ok(Size::Fit),
+            _ => Err(Diagnostic::new(
+                node,
+                format!("Unknown size identifier: {}", text),
+            )),
         },
-        _ => {
-            panic!("Unexpected size node kind: {}", node.kind());
-        }
+        _ => Err(Diagnostic::new(
+            node,
+            format!("Expected a size, found {}", node.kind()),
+        )),
     }
 }
 
 /// Parse a color value from a tree-sitter node.
-pub fn parse_color(node: Node, content: &[u8]) -> Color {
+pub fn parse_color(node: Node, content: &[u8]) -> Result<Color, Diagnostic> {
     match node.kind() {
         "hex_color" => {
             let text = str::from_utf8(&content[node.byte_range()]).unwrap();
-            Color::from_hex(text)
+            Ok(Color::from_hex(text))
         }
+        _ => Err(Diagnostic::new(
+            node,
+            format!("Expected a color, found {}", node.kind()),
+        )),
+    }
+}
+
+/// Parse a quoted string from a tree-sitter node, without its quotes & escapes.
+pub fn parse_string(node: Node, content: &[u8]) -> Result<String, Diagnostic> {
+    match node.kind() {
+        "string" => {
+            let text = str::from_utf8(&content[node.byte_range()]).unwrap();
+            let mut string = String::with_capacity(text.len());
+            let mut chars = text[1..text.len() - 1].chars();
+            while let Some(c) = chars.next() {
+                match c {
+                    '\\' => string.extend(chars.next()),
+                    _ => string.push(c),
+                }
+            }
+            Ok(string)
+        }
+        _ => Err(Diagnostic::new(
+            node,
+            format!("Expected a string, found {}", node.kind()),
+        )),
+    }
+}
+
+/// Parse a pixel amount from a tree-sitter node, which may be negative (e.g. an offset).
+pub fn parse_pixels(node: Node, content: &[u8]) -> Result<i32, Diagnostic> {
+    let text = str::from_utf8(&content[node.byte_range()]).unwrap();
+
+    match node.kind() {
+        "pixels" => text[..text.len() - 2]
+            .parse()
+            .map_err(|_| Diagnostic::new(node, format!("Invalid pixel amount: {}", text))),
+        _ => Err(Diagnostic::new(
+            node,
+            format!("Expected a pixel amount, found {}", node.kind()),
+        )),
+    }
+}
+
+/// Parse a non-negative pixel amount from a tree-sitter node, for sizes & widths.
+pub fn parse_length(node: Node, content: &[u8]) -> Result<i32, Diagnostic> {
+    match parse_pixels(node, content)? {
+        pixels if pixels < 0 => Err(Diagnostic::new(
+            node,
+            format!("Pixel amount cannot be negative: {}px", pixels),
+        )),
+        pixels => Ok(pixels),
+    }
+}
+
+/// Parse margin or padding insets from 1 to 4 pixel amounts, as in CSS shorthands:
+/// - `all`
+/// - `vertical horizontal`
+/// - `top horizontal bottom`
+/// - `top right bottom left`
+pub fn parse_insets(node: Node, values: &[Node], content: &[u8]) -> Result<Insets, Diagnostic> {
+    let pixels = values
+        .iter()
+        .map(|&value| parse_length(value, content))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    match pixels[..] {
+        [all] => Ok(Insets::uniform(all)),
+        [vertical, horizontal] => Ok(Insets::new(vertical, vertical, horizontal, horizontal)),
+        [top, horizontal, bottom] => Ok(Insets::new(top, bottom, horizontal, horizontal)),
+        [top, right, bottom, left] => Ok(Insets::new(top, bottom, left, right)),
+        _ => Err(Diagnostic::new(
+            node,
+            format!("Expected 1 to 4 insets, found {}", pixels.len()),
+        )),
+    }
+}
+
+/// Parse border widths from 1 to 4 pixel amounts, in the same order as insets
+pub fn parse_sides(node: Node, values: &[Node], content: &[u8]) -> Result<[f32; 4], Diagnostic> {
+    let insets = parse_insets(node, values, content)?;
+    let [left, top] = insets.start.map(|pixels| pixels as f32);
+    let [right, bottom] = insets.end.map(|pixels| pixels as f32);
+
+    Ok([top, right, bottom, left])
+}
+
+/// Parse corner radii from 1 to 4 pixel amounts, as in CSS shorthands:
+/// - `all`
+/// - `top-left-and-bottom-right top-right-and-bottom-left`
+/// - `top-left top-right-and-bottom-left bottom-right`
+/// - `top-left top-right bottom-right bottom-left`
+pub fn parse_corners(node: Node, values: &[Node], content: &[u8]) -> Result<[f32; 4], Diagnostic> {
+    let pixels = values
+        .iter()
+        .map(|&value| parse_length(value, content).map(|pixels| pixels as f32))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    match pixels[..] {
+        [all] => Ok([all; 4]),
+        [main, cross] => Ok([main, cross, main, cross]),
+        [top_left, cross, bottom_right] => Ok([top_left, cross, bottom_right, cross]),
+        [top_left, top_right, bottom_right, bottom_left] => {
+            Ok([top_left, top_right, bottom_right, bottom_left])
+        }
+        _ => Err(Diagnostic::new(
+            node,
+            format!("Expected 1 to 4 corner radii, found {}", pixels.len()),
+        )),
+    }
+}
+
+/// Parse a box shadow from 2 to 4 pixel amounts followed by a color, as in CSS:
+/// `offset-x offset-y [blur [spread]] color`
+pub fn parse_shadow(node: Node, values: &[Node], content: &[u8]) -> Result<Shadow, Diagnostic> {
+    let Some((&color, amounts)) = values.split_last() else {
+        return Err(Diagnostic::new(node, "Expected a shadow"));
+    };
+    let color = parse_color(color, content)?;
+    let pixels = amounts
+        .iter()
+        .map(|&value| parse_pixels(value, content).map(|pixels| pixels as f32))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let (offset, blur, spread) = match pixels[..] {
+        [x, y] => ([x, y], 0.0, 0.0),
+        [x, y, blur] => ([x, y], blur, 0.0),
+        [x, y, blur, spread] => ([x, y], blur, spread),
         _ => {
-            panic!("Unexpected color node kind: {}", node.kind());
+            return Err(Diagnostic::new(
+                node,
+                format!("Expected 2 to 4 shadow amounts, found {}", pixels.len()),
+            ));
+        }
+    };
+    if blur < 0.0 {
+        return Err(Diagnostic::new(
+            node,
+            "Shadow blur radius cannot be negative",
+        ));
+    }
+
+    Ok(Shadow {
+        offset,
+        blur,
+        spread,
+        color,
+    })
+}
+
+/// Parse a percentage from a tree-sitter node, as a fraction (e.g. 50% is 0.5).
+pub fn parse_percentage(node: Node, content: &[u8]) -> Result<f32, Diagnostic> {
+    let text = str::from_utf8(&content[node.byte_range()]).unwrap();
+
+    match node.kind() {
+        "percentage" => text[..text.len() - 1]
+            .parse()
+            .map(|percent: f32| percent / 100.0)
+            .map_err(|_| Diagnostic::new(node, format!("Invalid percentage: {}", text))),
+        _ => Err(Diagnostic::new(
+            node,
+            format!("Expected a percentage, found {}", node.kind()),
+        )),
+    }
+}
+
+/// Parse an angle in degrees from a tree-sitter node.
+pub fn parse_angle(node: Node, content: &[u8]) -> Result<f32, Diagnostic> {
+    let text = str::from_utf8(&content[node.byte_range()]).unwrap();
+
+    match node.kind() {
+        "angle" => text[..text.len() - 3]
+            .parse()
+            .map_err(|_| Diagnostic::new(node, format!("Invalid angle: {}", text))),
+        _ => Err(Diagnostic::new(
+            node,
+            format!("Expected an angle, found {}", node.kind()),
+        )),
+    }
+}
+
+/// Parse a gradient, followed by at least 2 color stops (a color, and an optional position):
+/// - `linear [angle] stops...`, from the top to the bottom by default
+/// - `radial [center-x center-y] radius stops...`, from the center by default
+///
+/// Stops without positions are spread evenly between their neighbors, as in CSS.
+pub fn parse_gradient(node: Node, values: &[Node], content: &[u8]) -> Result<Gradient, Diagnostic> {
+    let Some((&kind, mut values)) = values.split_first() else {
+        return Err(Diagnostic::new(node, "Expected a gradient"));
+    };
+
+    let kind = match identifier(kind, content) {
+        Some("linear") => {
+            let mut angle = 180.0;
+            if let [value, rest @ ..] = values
+                && value.kind() == "angle"
+            {
+                angle = parse_angle(*value, content)?;
+                values = rest;
+            }
+            GradientKind::Linear { angle }
+        }
+        Some("radial") => {
+            let mut center = [0.5, 0.5];
+            if let [x, y, rest @ ..] = values
+                && x.kind() == "percentage"
+            {
+                center = [
+                    parse_percentage(*x, content)?,
+                    parse_percentage(*y, content)?,
+                ];
+                values = rest;
+            }
+            let [radius, rest @ ..] = values else {
+                return Err(Diagnostic::new(node, "Expected a radial gradient radius"));
+            };
+            values = rest;
+            GradientKind::Radial {
+                center,
+                radius: parse_length(*radius, content)? as f32,
+            }
+        }
+        _ => return Err(expected(kind, content, "linear or radial")),
+    };
+
+    // Colors, with their position if any
+    let mut stops = Vec::<(Color, Option<f32>)>::new();
+    for &value in values {
+        match (value.kind(), stops.last_mut()) {
+            ("percentage", Some((_, position @ None))) => {
+                *position = Some(parse_percentage(value, content)?);
+            }
+            _ => stops.push((parse_color(value, content)?, None)),
+        }
+    }
+    if stops.len() < 2 {
+        return Err(Diagnostic::new(
+            node,
+            format!("Expected at least 2 gradient stops, found {}", stops.len()),
+        ));
+    }
+
+    // Spread stops without positions evenly, keeping positions increasing
+    let last = stops.len() - 1;
+    let mut positions = stops
+        .iter()
+        .map(|(_, position)| *position)
+        .collect::<Vec<_>>();
+    positions[0] = Some(positions[0].unwrap_or(0.0));
+    positions[last] = Some(positions[last].unwrap_or(1.0));
+    let mut previous = 0;
+    for i in 1..=last {
+        if let Some(end) = positions[i] {
+            let start = positions[previous].unwrap();
+            let gap = (i - previous) as f32;
+            for (j, position) in positions[previous + 1..i].iter_mut().enumerate() {
+                *position = Some(start + (end - start) * (j + 1) as f32 / gap);
+            }
+            previous = i;
         }
     }
+
+    let mut max = f32::MIN;
+    let stops = stops
+        .into_iter()
+        .zip(positions)
+        .map(|((color, _), position)| {
+            max = max.max(position.unwrap());
+            ColorStop {
+                color,
+                position: max,
+            }
+        })
+        .collect();
+
+    Ok(Gradient { kind, stops })
+}
+
+/// Parse a gap value from a tree-sitter node.
+pub fn parse_gap(node: Node, content: &[u8]) -> Result<Gap, Diagnostic> {
+    match identifier(node, content) {
+        Some("auto") => Ok(Gap::Auto),
+        _ => Ok(Gap::Fixed(parse_length(node, content)?)),
+    }
+}
+
+/// Parse an alignment value from a tree-sitter node.
+pub fn parse_align(node: Node, content: &[u8]) -> Result<Align, Diagnostic> {
+    match identifier(node, content) {
+        Some("start") => Ok(Align::Start),
+        Some("center") => Ok(Align::Center),
+        Some("end") => Ok(Align::End),
+        _ => Err(expected(node, content, "start, center or end")),
+    }
+}
+
+/// Parse a self alignment value from a tree-sitter node.
+pub fn parse_align_self(node: Node, content: &[u8]) -> Result<AlignSelf, Diagnostic> {
+    match identifier(node, content) {
+        Some("start") => Ok(AlignSelf::Start),
+        Some("center") => Ok(AlignSelf::Center),
+        Some("end") => Ok(AlignSelf::End),
+        Some("stretch") => Ok(AlignSelf::Stretch),
+        _ => Err(expected(node, content, "start, center, end or stretch")),
+    }
+}
+
+/// Parse a main axis justification value from a tree-sitter node.
+pub fn parse_justify(node: Node, content: &[u8]) -> Result<Justify, Diagnostic> {
+    match identifier(node, content) {
+        Some("start") => Ok(Justify::Start),
+        Some("center") => Ok(Justify::Center),
+        Some("end") => Ok(Justify::End),
+        Some("space_around") => Ok(Justify::SpaceAround),
+        Some("space_evenly") => Ok(Justify::SpaceEvenly),
+        _ => Err(expected(
+            node,
+            content,
+            "start, center, end, space_around or space_evenly",
+        )),
+    }
+}
+
+/// Parse an image fit value from a tree-sitter node.
+pub fn parse_fit(node: Node, content: &[u8]) -> Result<ImageFit, Diagnostic> {
+    match identifier(node, content) {
+        Some("fill") => Ok(ImageFit::Fill),
+        Some("contain") => Ok(ImageFit::Contain),
+        Some("cover") => Ok(ImageFit::Cover),
+        _ => Err(expected(node, content, "fill, contain or cover")),
+    }
+}
+
+/// Text of an identifier node, if the node is an identifier
+fn identifier<'a>(node: Node, content: &'a [u8]) -> Option<&'a str> {
+    match node.kind() {
+        "identifier" => Some(str::from_utf8(&content[node.byte_range()]).unwrap()),
+        _ => None,
+    }
+}
+
+/// Error for a value that is not one of the expected identifiers
+fn expected(node: Node, content: &[u8], identifiers: &str) -> Diagnostic {
+    let text = str::from_utf8(&content[node.byte_range()]).unwrap();
+    Diagnostic::new(node, format!("Expected {}, found {}", identifiers, text))
 }
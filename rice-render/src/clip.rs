@@ -0,0 +1,42 @@
+//! Clipping of elements to their scroll containers
+
+use rice_dom::DOM;
+
+/// Physical rectangle `[x, y, width, height]` an element is clipped to, for a screen of the
+/// given physical size & scale factor: the intersection of the boxes inside the borders of
+/// its scroll container ancestors, within the screen.
+///
+/// Returns none for elements outside of scroll containers. Pixels are drawn when they are
+/// inside the rectangle, as with a GPU scissor rectangle.
+pub fn clip_rect(dom: &DOM, index: usize, size: [u32; 2], scale: f32) -> Option<[u32; 4]> {
+    // Physical edges: left, top, right, bottom
+    let mut clip = [0.0, 0.0, size[0] as f32, size[1] as f32];
+    let mut clipped = false;
+
+    for idx in dom.ancestors(index) {
+        if !dom.layouts[idx].scroll.contains(&true) {
+            continue;
+        }
+
+        let rect = &dom.rects[idx];
+        let [top, right, bottom, left] = dom.styles[idx].border_width;
+        let [x, y] = rect.position.map(|p| p as f32);
+        let [width, height] = rect.size.map(|s| s as f32);
+
+        clip[0] = clip[0].max((x + left) * scale);
+        clip[1] = clip[1].max((y + top) * scale);
+        clip[2] = clip[2].min((x + width - right) * scale);
+        clip[3] = clip[3].min((y + height - bottom) * scale);
+        clipped = true;
+    }
+
+    let [left, top, right, bottom] = clip.map(f32::round);
+    clipped.then(|| {
+        [
+            left as u32,
+            top as u32,
+            (right - left).max(0.0) as u32,
+            (bottom - top).max(0.0) as u32,
+        ]
+    })
+}
@@ -0,0 +1,87 @@
+//! Placement of images in elements
+
+use rice_dom::ImageFit;
+
+/// Part of an image drawn in an element, and where
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Placement {
+    /// Offset & size of the drawn image within the element: x, y, width, height
+    pub rect: [f32; 4],
+    /// Drawn part of the image, as fractions of its size: left, top, right, bottom
+    pub uv: [f32; 4],
+}
+
+/// Place an image of the given size in pixels in an element of the given size
+pub fn fit_image(fit: ImageFit, image: [u32; 2], size: [f32; 2]) -> Placement {
+    let full = Placement {
+        rect: [0.0, 0.0, size[0], size[1]],
+        uv: [0.0, 0.0, 1.0, 1.0],
+    };
+    if image[0] == 0 || image[1] == 0 {
+        return full;
+    }
+
+    let scales = [size[0] / image[0] as f32, size[1] / image[1] as f32];
+    match fit {
+        ImageFit::Fill => full,
+        // Centered, with empty space on one axis
+        ImageFit::Contain => {
+            let scale = scales[0].min(scales[1]);
+            let [width, height] = [image[0] as f32 * scale, image[1] as f32 * scale];
+            Placement {
+                rect: [
+                    (size[0] - width) / 2.0,
+                    (size[1] - height) / 2.0,
+                    width,
+                    height,
+                ],
+                ..full
+            }
+        }
+        // Centered, cropped on one axis
+        ImageFit::Cover => {
+            let scale = scales[0].max(scales[1]);
+            let visible = [
+                size[0] / (image[0] as f32 * scale),
+                size[1] / (image[1] as f32 * scale),
+            ];
+            Placement {
+                uv: [
+                    (1.0 - visible[0]) / 2.0,
+                    (1.0 - visible[1]) / 2.0,
+                    (1.0 + visible[0]) / 2.0,
+                    (1.0 + visible[1]) / 2.0,
+                ],
+                ..full
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fit_image() {
+        // A 20x10 image in a 40x40 element
+        let fill = fit_image(ImageFit::Fill, [20, 10], [40.0, 40.0]);
+        assert_eq!(fill.rect, [0.0, 0.0, 40.0, 40.0]);
+        assert_eq!(fill.uv, [0.0, 0.0, 1.0, 1.0]);
+
+        // Scaled to 40x20, centered vertically
+        let contain = fit_image(ImageFit::Contain, [20, 10], [40.0, 40.0]);
+        assert_eq!(contain.rect, [0.0, 10.0, 40.0, 20.0]);
+        assert_eq!(contain.uv, [0.0, 0.0, 1.0, 1.0]);
+
+        // Scaled to 80x40, with the middle half visible horizontally
+        let cover = fit_image(ImageFit::Cover, [20, 10], [40.0, 40.0]);
+        assert_eq!(cover.rect, [0.0, 0.0, 40.0, 40.0]);
+        assert_eq!(cover.uv, [0.25, 0.0, 0.75, 1.0]);
+
+        // Empty images fill the element
+        for fit in [ImageFit::Contain, ImageFit::Cover] {
+            assert_eq!(fit_image(fit, [0, 10], [40.0, 40.0]), fill);
+        }
+    }
+}
@@ -0,0 +1,81 @@
+//! Rendered & drawn images, and their PNG encoding
+
+use std::{
+    fs::File,
+    io,
+    io::{BufReader, BufWriter},
+    path::Path,
+};
+
+/// Rendered or drawn image, as tightly packed sRGB RGBA pixels (row by row, from the top left)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl Image {
+    /// RGBA color of a pixel
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let i = ((y * self.width + x) * 4) as usize;
+        self.pixels[i..i + 4].try_into().unwrap()
+    }
+
+    /// Decode a PNG image, converting it to 8-bit RGBA
+    pub fn read_png<R: io::Read>(reader: R) -> Result<Self, png::DecodingError> {
+        let mut decoder = png::Decoder::new(reader);
+        decoder.set_transformations(png::Transformations::normalize_to_color8());
+
+        let mut reader = decoder.read_info()?;
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buffer)?;
+        let data = &buffer[..info.buffer_size()];
+
+        let pixels = match info.color_type {
+            png::ColorType::Rgba => data.to_vec(),
+            png::ColorType::Rgb => data
+                .chunks_exact(3)
+                .flat_map(|rgb| [rgb[0], rgb[1], rgb[2], 255])
+                .collect(),
+            png::ColorType::GrayscaleAlpha => data
+                .chunks_exact(2)
+                .flat_map(|ga| [ga[0], ga[0], ga[0], ga[1]])
+                .collect(),
+            // Indexed colors are expanded to RGB(A) by the decoder
+            png::ColorType::Grayscale | png::ColorType::Indexed => {
+                data.iter().flat_map(|&g| [g, g, g, 255]).collect()
+            }
+        };
+
+        Ok(Self {
+            width: info.width,
+            height: info.height,
+            pixels,
+        })
+    }
+
+    /// Load a PNG image file
+    pub fn open_png(path: impl AsRef<Path>) -> Result<Self, png::DecodingError> {
+        let file = File::open(path)?;
+        Self::read_png(BufReader::new(file))
+    }
+
+    /// Encode the image as PNG
+    pub fn write_png<W: io::Write>(&self, writer: W) -> Result<(), png::EncodingError> {
+        let mut encoder = png::Encoder::new(writer, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_source_srgb(png::SrgbRenderingIntent::Perceptual);
+
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.pixels)?;
+        Ok(())
+    }
+
+    /// Save the image to a PNG file
+    pub fn save_png(&self, path: impl AsRef<Path>) -> Result<(), png::EncodingError> {
+        let file = File::create(path)?;
+        self.write_png(BufWriter::new(file))
+    }
+}
@@ -0,0 +1,50 @@
+//! Rendering backend interface, shared by the GPU & CPU renderers
+
+mod clip;
+mod fit;
+mod image;
+
+use rice_dom::DOM;
+
+pub use clip::clip_rect;
+pub use fit::{Placement, fit_image};
+pub use image::Image;
+
+/// Backend drawing the elements of a DOM, independently of any windowing library.
+///
+/// Each `prepare` replaces the previously prepared elements, and `draw` only draws those,
+/// over the previous content of the target. Preparing the elements that need to be redrawn
+/// only is therefore limited to targets that keep their content between frames: targets
+/// that do not (e.g. swapchain textures) need the full draw list, by marking the root node
+/// dirty before `DOM::compute_redraw`.
+pub trait Renderer {
+    /// Backend resources needed to upload data (e.g. a device & queue)
+    type Context<'a>;
+    /// Target to draw the elements to (e.g. a render pass, or a pixel buffer)
+    type Target<'a>;
+
+    /// Update the physical size & scale factor of the screen
+    fn resize(&mut self, context: Self::Context<'_>, size: [u32; 2], scale: f32);
+
+    /// Upload the elements to draw (`DOM::redraw`), in drawing order,
+    /// replacing the previously prepared elements
+    fn prepare(&mut self, context: Self::Context<'_>, dom: &DOM);
+
+    /// Draw the prepared elements
+    fn draw(&self, target: &mut Self::Target<'_>);
+}
+
+/// Renderer drawing whole DOMs to images
+pub trait Offscreen {
+    /// Error while rendering
+    type Error;
+
+    /// Render the whole DOM to an image of the given physical size & scale factor.
+    /// The layout of the DOM must have been computed. Uncovered pixels are transparent.
+    fn render_image(
+        &mut self,
+        dom: &mut DOM,
+        size: [u32; 2],
+        scale: f32,
+    ) -> Result<Image, Self::Error>;
+}
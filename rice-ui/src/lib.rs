@@ -13,3 +13,11 @@ pub mod dom {
 pub mod parser {
     pub use rice_parser::*;
 }
+
+pub mod render {
+    pub use rice_render::*;
+}
+
+pub mod cpu {
+    pub use rice_cpu::*;
+}
@@ -0,0 +1,368 @@
+//! Texture atlas, packing the images drawn by elements in a single texture
+
+use std::collections::{HashMap, HashSet};
+
+use rice_render::Image;
+use wgpu::*;
+
+/// Format of the atlas texture, as decoded images are sRGB
+const FORMAT: TextureFormat = TextureFormat::Rgba8UnormSrgb;
+
+/// Initial physical size of the (square) atlas texture
+const INITIAL_SIZE: u32 = 256;
+
+/// Gap around each image, filled with its edge pixels so that filtering does not bleed
+const PADDING: u32 = 1;
+
+/// Area of an image in the atlas
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Region {
+    /// Position of the top left pixel in the atlas
+    pub position: [u32; 2],
+    /// Size of the image in pixels
+    pub size: [u32; 2],
+}
+
+/// Atlas of decoded RGBA images, by source.
+///
+/// Images are packed in rows ("shelves") from the top left, and the texture doubles in size
+/// when full, up to the max texture size of the device. The regions of replaced images are
+/// reused by the next images that fit in them.
+pub struct Atlas {
+    texture: Texture,
+    view: TextureView,
+    sampler: Sampler,
+    /// Physical size of the texture
+    size: u32,
+
+    /// Top left corner of the next image, in the current row
+    cursor: [u32; 2],
+    /// Height of the current row, with padding
+    row_height: u32,
+
+    /// Regions of the images, or none for images that could not be loaded or packed
+    regions: HashMap<String, Option<Region>>,
+    /// Sources whose images were loaded from files
+    files: HashSet<String>,
+    /// Regions of replaced images, free to be reused
+    free: Vec<Region>,
+}
+
+impl Atlas {
+    pub fn new(device: &Device) -> Self {
+        let texture = Self::create_texture(device, INITIAL_SIZE);
+        let view = texture.create_view(&TextureViewDescriptor::default());
+        let sampler = device.create_sampler(&SamplerDescriptor {
+            label: Some("Atlas Sampler"),
+            address_mode_u: AddressMode::ClampToEdge,
+            address_mode_v: AddressMode::ClampToEdge,
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Linear,
+            ..Default::default()
+        });
+
+        Self {
+            texture,
+            view,
+            sampler,
+            size: INITIAL_SIZE,
+            cursor: [0, 0],
+            row_height: 0,
+            regions: HashMap::new(),
+            files: HashSet::new(),
+            free: Vec::new(),
+        }
+    }
+
+    pub fn view(&self) -> &TextureView {
+        &self.view
+    }
+
+    pub fn sampler(&self) -> &Sampler {
+        &self.sampler
+    }
+
+    /// Physical size of the texture, which changes when the atlas grows
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    /// Region of the image of a source, if it was loaded
+    pub fn get(&self, source: &str) -> Option<Region> {
+        self.regions.get(source).copied().flatten()
+    }
+
+    /// Region of the image of a source, decoded from the PNG file at this path on first use.
+    /// Sources that cannot be loaded are not retried.
+    pub fn get_or_load(&mut self, device: &Device, queue: &Queue, source: &str) -> Option<Region> {
+        if let Some(region) = self.regions.get(source) {
+            return *region;
+        }
+
+        let region = Image::open_png(source)
+            .ok()
+            .and_then(|image| self.pack(device, queue, &image));
+        self.regions.insert(source.to_string(), region);
+        self.files.insert(source.to_string());
+        region
+    }
+
+    /// Add a decoded image for a source, replacing the previous image of this source.
+    /// The region of the previous image is reused if the sizes match, or freed otherwise.
+    pub fn insert(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        source: impl Into<String>,
+        image: &Image,
+    ) -> Option<Region> {
+        let source = source.into();
+        self.files.remove(&source);
+
+        let region = match self.regions.remove(&source).flatten() {
+            Some(region) if region.size == [image.width, image.height] => {
+                self.write(queue, image, region.position.map(|p| p - PADDING));
+                Some(region)
+            }
+            previous => {
+                self.free.extend(previous);
+                self.pack(device, queue, image)
+            }
+        };
+        self.regions.insert(source, region);
+        region
+    }
+
+    /// Forget the images loaded from files, freeing their regions, so that they are loaded
+    /// again on next use (e.g. after a hot reload)
+    pub fn clear_files(&mut self) {
+        for source in self.files.drain() {
+            if let Some(Some(region)) = self.regions.remove(&source) {
+                self.free.push(region);
+            }
+        }
+    }
+
+    /// Find room for an image and upload it, growing the texture if needed.
+    /// Free regions are reused first, in the order they were freed.
+    fn pack(&mut self, device: &Device, queue: &Queue, image: &Image) -> Option<Region> {
+        if image.width == 0 || image.height == 0 {
+            return None;
+        }
+
+        let fits =
+            |region: &Region| region.size[0] >= image.width && region.size[1] >= image.height;
+        if let Some(i) = self.free.iter().position(fits) {
+            let free = self.free.remove(i);
+            self.write(queue, image, free.position.map(|p| p - PADDING));
+            return Some(Region {
+                position: free.position,
+                size: [image.width, image.height],
+            });
+        }
+
+        let width = image.width + 2 * PADDING;
+        let height = image.height + 2 * PADDING;
+        let max = device.limits().max_texture_dimension_2d;
+
+        loop {
+            // Start a new row when the current one is full
+            if self.cursor[0] + width > self.size {
+                self.cursor = [0, self.cursor[1] + self.row_height];
+                self.row_height = 0;
+            }
+            if self.cursor[0] + width <= self.size && self.cursor[1] + height <= self.size {
+                break;
+            }
+            if self.size * 2 > max {
+                return None;
+            }
+            self.grow(device, queue);
+        }
+
+        let region = Region {
+            position: [self.cursor[0] + PADDING, self.cursor[1] + PADDING],
+            size: [image.width, image.height],
+        };
+        self.write(queue, image, self.cursor);
+        self.cursor[0] += width;
+        self.row_height = self.row_height.max(height);
+
+        Some(region)
+    }
+
+    /// Upload an image surrounded by copies of its edge pixels, from the given top left corner
+    /// of the padding
+    fn write(&self, queue: &Queue, image: &Image, origin: [u32; 2]) {
+        let width = image.width + 2 * PADDING;
+        let height = image.height + 2 * PADDING;
+
+        let mut pixels = Vec::with_capacity((width * height * 4) as usize);
+        for y in 0..height {
+            let y = y.saturating_sub(PADDING).min(image.height - 1);
+            for x in 0..width {
+                let x = x.saturating_sub(PADDING).min(image.width - 1);
+                pixels.extend_from_slice(&image.pixel(x, y));
+            }
+        }
+
+        queue.write_texture(
+            TexelCopyTextureInfo {
+                texture: &self.texture,
+                mip_level: 0,
+                origin: Origin3d {
+                    x: origin[0],
+                    y: origin[1],
+                    z: 0,
+                },
+                aspect: TextureAspect::All,
+            },
+            &pixels,
+            TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(width * 4),
+                rows_per_image: Some(height),
+            },
+            Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+        );
+    }
+
+    /// Double the size of the texture, keeping the packed images in place
+    fn grow(&mut self, device: &Device, queue: &Queue) {
+        let texture = Self::create_texture(device, self.size * 2);
+
+        let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("Atlas Growth Encoder"),
+        });
+        encoder.copy_texture_to_texture(
+            self.texture.as_image_copy(),
+            texture.as_image_copy(),
+            Extent3d {
+                width: self.size,
+                height: self.size,
+                depth_or_array_layers: 1,
+            },
+        );
+        queue.submit(Some(encoder.finish()));
+
+        self.view = texture.create_view(&TextureViewDescriptor::default());
+        self.texture = texture;
+        self.size *= 2;
+    }
+
+    fn create_texture(device: &Device, size: u32) -> Texture {
+        device.create_texture(&TextureDescriptor {
+            label: Some("Atlas Texture"),
+            size: Extent3d {
+                width: size,
+                height: size,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: FORMAT,
+            usage: TextureUsages::TEXTURE_BINDING
+                | TextureUsages::COPY_DST
+                | TextureUsages::COPY_SRC,
+            view_formats: &[],
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::init::init_headless;
+
+    /// Device & queue to test with, or none when no adapter is available
+    fn device() -> Option<(Device, Queue)> {
+        let device = pollster::block_on(init_headless()).ok();
+        if device.is_none() {
+            eprintln!("No adapter available, skipping");
+        }
+        device
+    }
+
+    /// Opaque white image
+    fn image(width: u32, height: u32) -> Image {
+        Image {
+            width,
+            height,
+            pixels: vec![255; (width * height * 4) as usize],
+        }
+    }
+
+    #[test]
+    fn test_pack() {
+        let Some((device, queue)) = device() else {
+            return;
+        };
+        let mut atlas = Atlas::new(&device);
+
+        // Images are packed in rows, with padding around them
+        let a = atlas.insert(&device, &queue, "a", &image(100, 50));
+        let b = atlas.insert(&device, &queue, "b", &image(100, 20));
+        let c = atlas.insert(&device, &queue, "c", &image(100, 10));
+        assert_eq!(a.map(|r| r.position), Some([1, 1]));
+        assert_eq!(b.map(|r| r.position), Some([103, 1]));
+        assert_eq!(c.map(|r| r.position), Some([1, 53]));
+        assert_eq!(atlas.get("b"), b);
+
+        // The texture grows when full
+        let d = atlas.insert(&device, &queue, "d", &image(300, 10));
+        assert_eq!(atlas.size(), 512);
+        assert_eq!(d.map(|r| r.position), Some([1, 65]));
+
+        // Empty images & missing files are not packed
+        assert_eq!(atlas.insert(&device, &queue, "empty", &image(0, 0)), None);
+        assert_eq!(atlas.get_or_load(&device, &queue, "missing.png"), None);
+        assert_eq!(atlas.get("missing.png"), None);
+    }
+
+    #[test]
+    fn test_replace() {
+        let Some((device, queue)) = device() else {
+            return;
+        };
+        let mut atlas = Atlas::new(&device);
+
+        // Images of the same size reuse the region of the image they replace
+        let first = atlas.insert(&device, &queue, "a", &image(10, 10));
+        assert_eq!(atlas.insert(&device, &queue, "a", &image(10, 10)), first);
+
+        // Otherwise, the region is freed for the next images that fit in it
+        let second = atlas.insert(&device, &queue, "a", &image(20, 10));
+        assert_eq!(second.map(|r| r.position), Some([13, 1]));
+        let b = atlas.insert(&device, &queue, "b", &image(8, 8));
+        assert_eq!(
+            b,
+            Some(Region {
+                position: [1, 1],
+                size: [8, 8],
+            })
+        );
+
+        // Images loaded from files are loaded again once cleared
+        let path = std::env::temp_dir().join("rice-atlas-test.png");
+        image(4, 4).save_png(&path).unwrap();
+        let source = path.to_str().unwrap();
+        let loaded = atlas.get_or_load(&device, &queue, source);
+        assert_eq!(loaded.map(|r| r.size), Some([4, 4]));
+
+        image(6, 6).save_png(&path).unwrap();
+        assert_eq!(atlas.get_or_load(&device, &queue, source), loaded);
+        atlas.clear_files();
+        assert_eq!(atlas.get(source), None);
+        let reloaded = atlas.get_or_load(&device, &queue, source);
+        assert_eq!(reloaded.map(|r| r.size), Some([6, 6]));
+        std::fs::remove_file(path).unwrap();
+
+        // Inserted images are kept
+        assert_eq!(atlas.get("b"), b);
+    }
+}
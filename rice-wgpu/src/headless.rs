@@ -0,0 +1,226 @@
+//! Offscreen rendering, without a window (e.g. for screenshot tests)
+
+use std::{fmt, sync::mpsc};
+
+use rice_dom::DOM;
+use rice_render::{Image, Offscreen, Renderer};
+use wgpu::*;
+
+use crate::{init::init_headless, pipeline::Pipeline};
+
+/// Format of the offscreen texture, as read back in images
+const FORMAT: TextureFormat = TextureFormat::Rgba8UnormSrgb;
+
+/// Error while rendering offscreen
+#[derive(Debug)]
+pub enum HeadlessError {
+    /// No adapter is available, not even a software one
+    Adapter(RequestAdapterError),
+    /// The device could not be created
+    Device(RequestDeviceError),
+    /// The rendered texture could not be read back
+    Map(BufferAsyncError),
+}
+
+impl fmt::Display for HeadlessError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HeadlessError::Adapter(error) => write!(f, "Failed to find an adapter: {}", error),
+            HeadlessError::Device(error) => write!(f, "Failed to create device: {}", error),
+            HeadlessError::Map(error) => write!(f, "Failed to read back texture: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for HeadlessError {}
+
+impl From<RequestAdapterError> for HeadlessError {
+    fn from(error: RequestAdapterError) -> Self {
+        HeadlessError::Adapter(error)
+    }
+}
+
+impl From<RequestDeviceError> for HeadlessError {
+    fn from(error: RequestDeviceError) -> Self {
+        HeadlessError::Device(error)
+    }
+}
+
+impl From<BufferAsyncError> for HeadlessError {
+    fn from(error: BufferAsyncError) -> Self {
+        HeadlessError::Map(error)
+    }
+}
+
+/// Offscreen renderer, reusing its device & pipeline between renders
+pub struct Headless {
+    device: Device,
+    queue: Queue,
+    pipeline: Pipeline,
+}
+
+impl Headless {
+    pub async fn new() -> Result<Self, HeadlessError> {
+        let (device, queue) = init_headless().await?;
+        let pipeline = Pipeline::new(&device, [1, 1], 1.0, FORMAT, 100);
+
+        Ok(Self {
+            device,
+            queue,
+            pipeline,
+        })
+    }
+
+    /// Add a decoded image for a source, as `Pipeline::insert_image`
+    pub fn insert_image(&mut self, source: impl Into<String>, image: &Image) -> bool {
+        self.pipeline
+            .insert_image(&self.device, &self.queue, source, image)
+    }
+}
+
+impl Offscreen for Headless {
+    type Error = HeadlessError;
+
+    fn render_image(
+        &mut self,
+        dom: &mut DOM,
+        size: [u32; 2],
+        scale: f32,
+    ) -> Result<Image, HeadlessError> {
+        let [width, height] = [size[0].max(1), size[1].max(1)];
+
+        // Elements to draw
+        dom.dirty.push(dom.root);
+        dom.compute_redraw();
+        let context = (&self.device, &self.queue);
+        self.pipeline.resize(context, [width, height], scale);
+        self.pipeline.prepare(context, dom);
+        dom.dirty.clear();
+        dom.redraw.clear();
+        dom.reload_images = false;
+
+        let extent = Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        };
+        let texture = self.device.create_texture(&TextureDescriptor {
+            label: Some("Offscreen Texture"),
+            size: extent,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: FORMAT,
+            usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&TextureViewDescriptor::default());
+
+        // Rows of the read back buffer must be aligned
+        let row = width * 4;
+        let padded_row = row.div_ceil(COPY_BYTES_PER_ROW_ALIGNMENT) * COPY_BYTES_PER_ROW_ALIGNMENT;
+        let buffer = self.device.create_buffer(&BufferDescriptor {
+            label: Some("Readback Buffer"),
+            size: (padded_row * height) as u64,
+            usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&CommandEncoderDescriptor { label: None });
+        self.pipeline
+            .render_to_view(&mut encoder, &view, LoadOp::Clear(Color::TRANSPARENT));
+        encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            TexelCopyBufferInfo {
+                buffer: &buffer,
+                layout: TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_row),
+                    rows_per_image: Some(height),
+                },
+            },
+            extent,
+        );
+        self.queue.submit(Some(encoder.finish()));
+
+        // Wait for the copy, then strip the row padding
+        let slice = buffer.slice(..);
+        let (sender, receiver) = mpsc::channel();
+        slice.map_async(MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        let _ = self.device.poll(PollType::Wait);
+        receiver
+            .recv()
+            .expect("Readback buffer mapping was dropped")?;
+
+        let mut pixels = Vec::with_capacity((row * height) as usize);
+        {
+            let data = slice.get_mapped_range();
+            for chunk in data.chunks(padded_row as usize) {
+                pixels.extend_from_slice(&chunk[..row as usize]);
+            }
+        }
+        buffer.unmap();
+
+        Ok(Image {
+            width,
+            height,
+            pixels,
+        })
+    }
+}
+
+/// Render the whole DOM to an image of the given physical size & scale factor, with a new
+/// offscreen renderer. Use `Headless` to render several images.
+pub async fn render_image(
+    dom: &mut DOM,
+    size: [u32; 2],
+    scale: f32,
+) -> Result<Image, HeadlessError> {
+    Headless::new().await?.render_image(dom, size, scale)
+}
+
+#[cfg(test)]
+mod tests {
+    use rice_dom::{ImageFit, StyleProp, StyleSheet, StyleValue};
+    use rice_layout::{Layout, Monospace, Size};
+
+    use super::*;
+
+    #[test]
+    fn test_image_contain() {
+        let Ok(mut headless) = pollster::block_on(Headless::new()) else {
+            eprintln!("No adapter available, skipping");
+            return;
+        };
+
+        let mut dom = DOM::new();
+        let mut stylesheet = StyleSheet::default();
+        stylesheet
+            .default
+            .insert(StyleProp::Image, StyleValue::Image("flag".to_string()));
+        stylesheet
+            .default
+            .insert(StyleProp::ImageFit, StyleValue::Fit(ImageFit::Contain));
+        dom.root = dom.insert(Layout::new(Size::Fixed(10), Size::Fixed(10)), stylesheet);
+        dom.compute_layout(dom.root, &Monospace::new(10, 20));
+
+        // Red & blue halves
+        let flag = Image {
+            width: 2,
+            height: 1,
+            pixels: vec![255, 0, 0, 255, 0, 0, 255, 255],
+        };
+        assert!(headless.insert_image("flag", &flag));
+        let image = headless.render_image(&mut dom, [10, 10], 1.0).unwrap();
+
+        // Centered vertically, with the edges of the image repeated (not the atlas padding)
+        assert_eq!(image.pixel(0, 5), [255, 0, 0, 255]);
+        assert_eq!(image.pixel(9, 5), [0, 0, 255, 255]);
+        assert_eq!(image.pixel(5, 0), [0, 0, 0, 0]);
+        assert_eq!(image.pixel(5, 9), [0, 0, 0, 0]);
+    }
+}
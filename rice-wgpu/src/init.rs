@@ -3,6 +3,8 @@
 use wgpu::*;
 use winit::window::Window;
 
+use crate::HeadlessError;
+
 /// Initialize WGPU for the given window
 pub async fn init_wgpu<'a>(
     window: &'a Window,
@@ -31,20 +33,7 @@ pub async fn init_wgpu<'a>(
         .await
         .expect("Failed to find an appropriate adapter");
 
-    // Create the logical device and command queue
-    let mut limits = Limits::downlevel_webgl2_defaults();
-    limits.max_storage_buffers_per_shader_stage = 1;
-    limits.max_storage_buffer_binding_size = 16 * 1024 * 1024; // 16 MB
-
-    // Create device & queue
-    let (device, queue) = adapter
-        .request_device(&DeviceDescriptor {
-            label: None,
-            required_features: Features::empty(),
-            required_limits: limits,
-            memory_hints: MemoryHints::MemoryUsage,
-            trace: Trace::Off,
-        })
+    let (device, queue) = request_device(&adapter)
         .await
         .expect("Failed to create device");
 
@@ -58,3 +47,43 @@ pub async fn init_wgpu<'a>(
 
     (device, queue, surface, swapchain_format, config)
 }
+
+/// Initialize WGPU without a window, for offscreen rendering.
+///
+/// The adapter can be chosen with the `WGPU_ADAPTER_NAME` & `WGPU_BACKEND` environment variables.
+/// Falls back to a software adapter (if any) when no hardware adapter is available.
+pub async fn init_headless() -> Result<(Device, Queue), HeadlessError> {
+    let instance = Instance::new(&wgpu::InstanceDescriptor::from_env_or_default());
+
+    let adapter = match util::initialize_adapter_from_env_or_default(&instance, None).await {
+        Ok(adapter) => adapter,
+        Err(_) => {
+            instance
+                .request_adapter(&RequestAdapterOptions {
+                    power_preference: PowerPreference::default(),
+                    force_fallback_adapter: true,
+                    compatible_surface: None,
+                })
+                .await?
+        }
+    };
+
+    Ok(request_device(&adapter).await?)
+}
+
+/// Create the logical device and command queue
+async fn request_device(adapter: &Adapter) -> Result<(Device, Queue), RequestDeviceError> {
+    let mut limits = Limits::downlevel_webgl2_defaults();
+    limits.max_storage_buffers_per_shader_stage = 1;
+    limits.max_storage_buffer_binding_size = 16 * 1024 * 1024; // 16 MB
+
+    adapter
+        .request_device(&DeviceDescriptor {
+            label: None,
+            required_features: Features::empty(),
+            required_limits: limits,
+            memory_hints: MemoryHints::MemoryUsage,
+            trace: Trace::Off,
+        })
+        .await
+}
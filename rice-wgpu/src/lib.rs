@@ -1,26 +1,65 @@
+mod atlas;
+mod headless;
 mod init;
 mod pipeline;
+mod renderer;
 mod write_buffer;
 
+use std::time::{Duration, Instant};
+
 use rice_dom::DOM;
+use rice_layout::Measure;
 use winit::{
-    dpi::LogicalPosition,
-    event::{ElementState, Event, MouseButton, WindowEvent},
-    event_loop::EventLoop,
+    event::{Event, WindowEvent},
+    event_loop::{ControlFlow, EventLoop},
     window::Window,
 };
 
-use crate::{init::init_wgpu, pipeline::Pipeline};
+use crate::init::init_wgpu;
+
+pub use atlas::{Atlas, Region};
+pub use headless::{Headless, HeadlessError, render_image};
+pub use pipeline::Pipeline;
+pub use renderer::RiceRenderer;
 
-pub async fn run(event_loop: EventLoop<()>, window: Window, mut dom: DOM) {
+/// Run the UI, laying out text with the given glyph metrics
+pub async fn run(
+    event_loop: EventLoop<()>,
+    window: Window,
+    dom: DOM,
+    measure: impl Measure + 'static,
+) {
+    run_with_updates(event_loop, window, dom, measure, None, |_| false).await
+}
+
+/// Run the UI, calling `update` on the DOM at the given interval (e.g. to hot reload it).
+/// The DOM is redrawn when `update` returns true, or when it marked nodes for relayout.
+///
+/// To drive the event loop from another application, use `RiceRenderer` instead.
+pub async fn run_with_updates(
+    event_loop: EventLoop<()>,
+    window: Window,
+    dom: DOM,
+    measure: impl Measure + 'static,
+    interval: Option<Duration>,
+    mut update: impl FnMut(&mut DOM) -> bool,
+) {
     let window = &window;
     let (device, queue, surface, format, mut config) = init_wgpu(window).await;
 
-    let mut pipeline = Pipeline::new(&device, window, format, 100);
-    dom.dirty.push(dom.root); // Mark the root node as dirty
+    let mut rice = RiceRenderer::new(&device, format, dom, measure, screen(window), scale(window));
 
     event_loop
         .run(move |event, target| {
+            // Update the DOM when idle, and wake up for the next update
+            if let (Event::AboutToWait, Some(interval)) = (&event, interval) {
+                if update(&mut rice.dom) || rice.needs_redraw() {
+                    window.request_redraw();
+                }
+                target.set_control_flow(ControlFlow::WaitUntil(Instant::now() + interval));
+                return;
+            }
+
             if let Event::WindowEvent { event, .. } = event {
                 match event {
                     WindowEvent::Resized(new_size) => {
@@ -28,20 +67,11 @@ pub async fn run(event_loop: EventLoop<()>, window: Window, mut dom: DOM) {
                         config.width = new_size.width.max(1);
                         config.height = new_size.height.max(1);
                         surface.configure(&device, &config);
-                        pipeline.update_screen(&queue, window);
+                        rice.handle_window_event(&event);
                         window.request_redraw();
-                        dom.dirty.push(dom.root); // Mark the root node as dirty
                     }
                     WindowEvent::RedrawRequested => {
-                        // TODO : decide of redrawing, depending on dirty elements, screen changed, etc
-                        if dom.dirty.is_empty() {
-                            return;
-                        }
-                        dom.compute_redraw();
-                        pipeline.update_elements(&device, &queue, &dom);
-                        let n = dom.redraw.len();
-                        dom.dirty.clear();
-                        dom.redraw.clear();
+                        rice.prepare(&device, &queue);
 
                         let frame = surface
                             .get_current_texture()
@@ -53,65 +83,35 @@ pub async fn run(event_loop: EventLoop<()>, window: Window, mut dom: DOM) {
                             device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
                                 label: None,
                             });
-                        {
-                            let mut rpass =
-                                encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                                    label: None,
-                                    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                                        view: &view,
-                                        depth_slice: None,
-                                        resolve_target: None,
-                                        ops: wgpu::Operations {
-                                            load: wgpu::LoadOp::Load,
-                                            store: wgpu::StoreOp::Store,
-                                        },
-                                    })],
-                                    depth_stencil_attachment: None,
-                                    timestamp_writes: None,
-                                    occlusion_query_set: None,
-                                });
-
-                            pipeline.render(&mut rpass);
-                        }
+                        rice.render_to_view(
+                            &mut encoder,
+                            &view,
+                            wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                        );
 
                         queue.submit(Some(encoder.finish()));
                         window.pre_present_notify();
                         frame.present();
                     }
                     WindowEvent::CloseRequested => target.exit(),
-                    WindowEvent::CursorMoved { position, .. } => {
-                        let position: LogicalPosition<i32> =
-                            position.to_logical(window.scale_factor());
-                        let mouse = [position.x, position.y];
-
-                        dom.handle_mouse_moved(mouse);
-                        window.request_redraw();
-                    }
-                    WindowEvent::CursorLeft { .. } => {
-                        dom.reset_mouse();
-                        if dom.dirty.len() > 0 {
-                            window.request_redraw();
-                        }
-                    }
-                    WindowEvent::MouseInput { state, button, .. } => {
-                        match button {
-                            MouseButton::Left => match state {
-                                ElementState::Pressed => {
-                                    dom.handle_mouse_clicked(true);
-                                }
-                                ElementState::Released => {
-                                    dom.handle_mouse_clicked(false);
-                                }
-                            },
-                            _ => {}
-                        };
-                        if dom.dirty.len() > 0 {
+                    event => {
+                        if rice.handle_window_event(&event) {
                             window.request_redraw();
                         }
                     }
-                    _ => {}
                 };
             }
         })
         .unwrap();
 }
+
+/// Physical size of the window
+fn screen(window: &Window) -> [u32; 2] {
+    let size = window.inner_size();
+    [size.width, size.height]
+}
+
+/// Scale factor of the window
+fn scale(window: &Window) -> f32 {
+    window.scale_factor() as f32
+}
@@ -1,16 +1,19 @@
 //! Drawing pipeline manager
 
-use std::borrow::Cow;
+use std::{borrow::Cow, ops::Range};
 
 use rice_dom::{ComputedStyle, DOM};
 use rice_layout::Rect;
+use rice_render::{Image, Renderer, clip_rect, fit_image};
 use wgpu::{
     util::{BufferInitDescriptor, DeviceExt},
     *,
 };
-use winit::window::Window;
 
-use crate::write_buffer::{WriteBuffer, write_indexed_slice_to_buffer};
+use crate::{
+    atlas::Atlas,
+    write_buffer::{WriteBuffer, write_indexed_slice_to_buffer},
+};
 
 /// Vertex indices to draw a rectangle from 2 triangles
 const INDICES: &[u16] = &[0, 1, 2, 2, 3, 0];
@@ -23,12 +26,26 @@ const VERTICES: &[f32] = &[
     -1.0, 1.0, // top left
 ];
 
+/// Layer of an instance drawing an element
+const ELEMENT: u32 = 0;
+/// Layer of an instance drawing the shadow of an element, beneath it
+const SHADOW: u32 = 1;
+/// Layer of an instance drawing the image of an element, above it
+const IMAGE: u32 = 2;
+
 /// Rendering pipeline manager
 pub struct Pipeline {
     pub pipeline: RenderPipeline,
 
-    /// Amount of elements to draw
+    /// Amount of instances to draw (elements & their shadows)
     pub n: u32,
+    /// Ranges of instances drawn together, with the physical rectangle they are clipped to
+    /// (see `clip_rect`), in drawing order
+    pub draws: Vec<(Range<u32>, Option<[u32; 4]>)>,
+    /// Physical size of the screen
+    screen: [u32; 2],
+    /// Logical to physical scaling factor
+    scale: f32,
 
     /// Screen size & conversion physical <-> logical
     pub screen_buffer: Buffer,
@@ -38,34 +55,50 @@ pub struct Pipeline {
     pub rects_buffer: Buffer,
     /// Instance buffer (styles)
     pub styles_buffer: Buffer,
+    /// Instance buffer (layers & ranges of gradient stops)
+    pub instances_buffer: Buffer,
+    /// Instance buffer (image placements in the elements & the atlas, and tints)
+    pub images_buffer: Buffer,
+    /// Colors & positions of the gradient stops of all instances
+    pub stops_buffer: Buffer,
     /// Base rectangle index buffer
     pub index_buffer: Buffer,
 
-    /// Bind group for the timer & screen
+    /// Bind group for the timer & screen, and the gradient stops
     pub uniforms_group: BindGroup,
+    uniforms_layout: BindGroupLayout,
 
-    /// Max amount of rects that can fit in the vertex & rects buffers
+    /// Images drawn by the elements
+    pub atlas: Atlas,
+    /// Bind group for the atlas texture & sampler
+    pub atlas_group: BindGroup,
+    atlas_layout: BindGroupLayout,
+
+    /// Max amount of instances that can fit in the instance buffers
     pub size: usize,
+    /// Max amount of gradient stops that can fit in the stops buffer
+    pub stops_size: usize,
 }
 
 impl Pipeline {
-    /// Create a new bind group manager with the given max amount of rects
+    /// Create a new bind group manager with the given max amount of rects, for a screen of the
+    /// given physical size & scale factor
     pub fn new(
         device: &Device,
-        window: &Window,
+        screen: [u32; 2],
+        scale: f32,
         swapchain_format: TextureFormat,
         size: usize,
     ) -> Self {
         // ***************************************** //
         //                  BUFFERS                  //
         // ***************************************** //
-        let screen = window.inner_size();
         let screen_buffer = device.create_buffer_init(&BufferInitDescriptor {
             label: Some("Screen Buffer"),
             contents: bytemuck::cast_slice(&[
-                screen.width as f32,
-                screen.height as f32,
-                window.scale_factor() as f32,
+                screen[0] as f32,
+                screen[1] as f32,
+                scale,
                 0.0f32, // padding
             ]),
             usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
@@ -82,23 +115,59 @@ impl Pipeline {
         });
         let rects_buffer = Self::create_rects_buffer(device, size);
         let styles_buffer = Self::create_styles_buffer(device, size);
+        let instances_buffer = Self::create_instances_buffer(device, size);
+        let images_buffer = Self::create_images_buffer(device, size);
+        let stops_buffer = Self::create_stops_buffer(device, size);
 
         // ***************************************** //
         //             BIND GROUP LAYOUTS            //
         // ***************************************** //
         let uniforms_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
-            entries: &[BindGroupLayoutEntry {
-                binding: 0,
-                visibility: ShaderStages::VERTEX | ShaderStages::FRAGMENT,
-                ty: BindingType::Buffer {
-                    ty: BufferBindingType::Uniform,
-                    has_dynamic_offset: false,
-                    min_binding_size: None,
+            entries: &[
+                BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::VERTEX | ShaderStages::FRAGMENT,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
                 },
-                count: None,
-            }],
+                BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
             label: Some("Uniforms Bind Group Layout"),
         });
+        let atlas_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            entries: &[
+                BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Texture {
+                        sample_type: TextureSampleType::Float { filterable: true },
+                        view_dimension: TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Sampler(SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+            label: Some("Atlas Bind Group Layout"),
+        });
         let vertex_layout = VertexBufferLayout {
             array_stride: std::mem::size_of::<[f32; 2]>() as BufferAddress,
             step_mode: VertexStepMode::Vertex,
@@ -129,25 +198,101 @@ impl Pipeline {
         let styles_layout = VertexBufferLayout {
             array_stride: ComputedStyle::SIZE as BufferAddress,
             step_mode: VertexStepMode::Instance,
-            // RGBA color
-            attributes: &[VertexAttribute {
-                format: VertexFormat::Float32x4,
-                offset: 0,
-                shader_location: 3,
-            }],
+            attributes: &[
+                // RGBA color
+                VertexAttribute {
+                    format: VertexFormat::Float32x4,
+                    offset: 0,
+                    shader_location: 3,
+                },
+                // Corner radii
+                VertexAttribute {
+                    format: VertexFormat::Float32x4,
+                    offset: std::mem::size_of::<[f32; 4]>() as BufferAddress,
+                    shader_location: 4,
+                },
+                // Border widths
+                VertexAttribute {
+                    format: VertexFormat::Float32x4,
+                    offset: 2 * std::mem::size_of::<[f32; 4]>() as BufferAddress,
+                    shader_location: 5,
+                },
+                // Border RGBA color
+                VertexAttribute {
+                    format: VertexFormat::Float32x4,
+                    offset: 3 * std::mem::size_of::<[f32; 4]>() as BufferAddress,
+                    shader_location: 6,
+                },
+                // Shadow offset, blur radius & spread
+                VertexAttribute {
+                    format: VertexFormat::Float32x4,
+                    offset: 4 * std::mem::size_of::<[f32; 4]>() as BufferAddress,
+                    shader_location: 7,
+                },
+                // Shadow RGBA color
+                VertexAttribute {
+                    format: VertexFormat::Float32x4,
+                    offset: 5 * std::mem::size_of::<[f32; 4]>() as BufferAddress,
+                    shader_location: 8,
+                },
+                // Gradient kind & parameters
+                VertexAttribute {
+                    format: VertexFormat::Float32x4,
+                    offset: 6 * std::mem::size_of::<[f32; 4]>() as BufferAddress,
+                    shader_location: 10,
+                },
+            ],
+        };
+        let instances_layout = VertexBufferLayout {
+            array_stride: std::mem::size_of::<[u32; 3]>() as BufferAddress,
+            step_mode: VertexStepMode::Instance,
+            attributes: &[
+                // Layer
+                VertexAttribute {
+                    format: VertexFormat::Uint32,
+                    offset: 0,
+                    shader_location: 9,
+                },
+                // First gradient stop & amount of stops
+                VertexAttribute {
+                    format: VertexFormat::Uint32x2,
+                    offset: std::mem::size_of::<u32>() as BufferAddress,
+                    shader_location: 11,
+                },
+            ],
+        };
+        let images_layout = VertexBufferLayout {
+            array_stride: std::mem::size_of::<[f32; 12]>() as BufferAddress,
+            step_mode: VertexStepMode::Instance,
+            attributes: &[
+                // Logical offset & size of the image within the element
+                VertexAttribute {
+                    format: VertexFormat::Float32x4,
+                    offset: 0,
+                    shader_location: 12,
+                },
+                // Drawn part of the atlas: left, top, right, bottom
+                VertexAttribute {
+                    format: VertexFormat::Float32x4,
+                    offset: std::mem::size_of::<[f32; 4]>() as BufferAddress,
+                    shader_location: 13,
+                },
+                // RGBA tint
+                VertexAttribute {
+                    format: VertexFormat::Float32x4,
+                    offset: 2 * std::mem::size_of::<[f32; 4]>() as BufferAddress,
+                    shader_location: 14,
+                },
+            ],
         };
 
         // ***************************************** //
         //                 BIND GROUPS               //
         // ***************************************** //
-        let uniforms_group = device.create_bind_group(&BindGroupDescriptor {
-            layout: &uniforms_layout,
-            entries: &[BindGroupEntry {
-                binding: 0,
-                resource: screen_buffer.as_entire_binding(),
-            }],
-            label: Some("Uniforms Bind Group"),
-        });
+        let uniforms_group =
+            Self::create_uniforms_group(device, &uniforms_layout, &screen_buffer, &stops_buffer);
+        let atlas = Atlas::new(device);
+        let atlas_group = Self::create_atlas_group(device, &atlas_layout, &atlas);
 
         // ***************************************** //
         //                  SHADERS                  //
@@ -162,7 +307,7 @@ impl Pipeline {
         // ***************************************** //
         let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
             label: Some("Pipeline Layout"),
-            bind_group_layouts: &[&uniforms_layout],
+            bind_group_layouts: &[&uniforms_layout, &atlas_layout],
             push_constant_ranges: &[],
         });
 
@@ -172,7 +317,13 @@ impl Pipeline {
             vertex: wgpu::VertexState {
                 module: &shader,
                 entry_point: None,
-                buffers: &[vertex_layout, rects_layout, styles_layout],
+                buffers: &[
+                    vertex_layout,
+                    rects_layout,
+                    styles_layout,
+                    instances_layout,
+                    images_layout,
+                ],
                 compilation_options: Default::default(),
             },
             fragment: Some(wgpu::FragmentState {
@@ -199,16 +350,28 @@ impl Pipeline {
         Self {
             pipeline,
             n: 0,
+            draws: Vec::new(),
+            screen,
+            scale,
 
             screen_buffer,
             vertex_buffer,
             rects_buffer,
             styles_buffer,
+            instances_buffer,
+            images_buffer,
+            stops_buffer,
             index_buffer,
 
             uniforms_group,
+            uniforms_layout,
+
+            atlas,
+            atlas_group,
+            atlas_layout,
 
             size,
+            stops_size: size,
         }
     }
 
@@ -219,6 +382,7 @@ impl Pipeline {
 
         // Uniforms
         render_pass.set_bind_group(0, &self.uniforms_group, &[]);
+        render_pass.set_bind_group(1, &self.atlas_group, &[]);
 
         // Index buffer
         render_pass.set_index_buffer(self.index_buffer.slice(..), IndexFormat::Uint16);
@@ -227,20 +391,54 @@ impl Pipeline {
         render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
         render_pass.set_vertex_buffer(1, self.rects_buffer.slice(..));
         render_pass.set_vertex_buffer(2, self.styles_buffer.slice(..));
+        render_pass.set_vertex_buffer(3, self.instances_buffer.slice(..));
+        render_pass.set_vertex_buffer(4, self.images_buffer.slice(..));
+
+        // Draw calls, clipping the instances within scroll containers
+        for (instances, clip) in &self.draws {
+            let [x, y, width, height] = clip.unwrap_or([0, 0, self.screen[0], self.screen[1]]);
+            render_pass.set_scissor_rect(x, y, width, height);
+            render_pass.draw_indexed(0..6, 0, instances.clone());
+        }
+    }
+
+    /// Draw the elements to a texture view, in a new render pass
+    pub fn render_to_view(
+        &self,
+        encoder: &mut CommandEncoder,
+        view: &TextureView,
+        load: LoadOp<Color>,
+    ) {
+        let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+            label: None,
+            color_attachments: &[Some(RenderPassColorAttachment {
+                view,
+                depth_slice: None,
+                resolve_target: None,
+                ops: Operations {
+                    load,
+                    store: StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
 
-        // Draw call
-        render_pass.draw_indexed(0..6, 0, 0..self.n);
+        self.render(&mut render_pass);
     }
 
-    /// Update screen size & scale
-    pub fn update_screen(&self, queue: &Queue, window: &Window) {
+    /// Update screen physical size & scale
+    pub fn update_screen(&mut self, queue: &Queue, screen: [u32; 2], scale: f32) {
+        self.screen = screen;
+        self.scale = scale;
         queue.write_buffer(
             &self.screen_buffer,
             0,
             bytemuck::cast_slice(&[
-                window.inner_size().width as f32,
-                window.inner_size().height as f32,
-                window.scale_factor() as f32,
+                screen[0] as f32,
+                screen[1] as f32,
+                scale,
                 0.0f32, // padding
             ]),
         );
@@ -251,21 +449,164 @@ impl Pipeline {
         assert!(dom.rects.len() == dom.styles.len());
         assert!(dom.rects.len() > 0);
 
-        let n = dom.redraw.len();
+        // Load the images first, as the atlas may grow
+        let atlas_size = self.atlas.size();
+        if dom.reload_images {
+            self.atlas.clear_files();
+        }
+        for &index in &dom.redraw {
+            if let Some(source) = &dom.styles[index].image {
+                self.atlas.get_or_load(device, queue, source);
+            }
+        }
+        if self.atlas.size() != atlas_size {
+            self.atlas_group = Self::create_atlas_group(device, &self.atlas_layout, &self.atlas);
+        }
+
+        // Instances to draw, with the shadow of each element right beneath it and its image
+        // right above it. Each instance has a layer, the range of its gradient stops, and the
+        // placement of its image. Consecutive instances with the same clip rectangle are drawn
+        // together, and elements clipped out entirely are skipped.
+        let mut indices = Vec::with_capacity(dom.redraw.len());
+        let mut instances = Vec::with_capacity(dom.redraw.len());
+        let mut images = Vec::with_capacity(dom.redraw.len());
+        let mut stops = Vec::new();
+        self.draws.clear();
+        for &index in &dom.redraw {
+            let clip = clip_rect(dom, index, self.screen, self.scale);
+            if let Some([_, _, 0, _] | [_, _, _, 0]) = clip {
+                continue;
+            }
+            if self.draws.last().is_none_or(|(_, last)| *last != clip) {
+                let start = indices.len() as u32;
+                self.draws.push((start..start, clip));
+            }
+
+            let style = &dom.styles[index];
+            if style.shadow.color.a > 0.0 {
+                indices.push(index);
+                instances.push([SHADOW, 0, 0]);
+                images.push([0.0; 12]);
+            }
+
+            let first = stops.len();
+            for stop in style.background_gradient.iter().flat_map(|g| &g.stops) {
+                let color = &stop.color;
+                stops.push([
+                    color.r,
+                    color.g,
+                    color.b,
+                    color.a,
+                    stop.position,
+                    0.0,
+                    0.0,
+                    0.0,
+                ]);
+            }
+            indices.push(index);
+            instances.push([ELEMENT, first as u32, (stops.len() - first) as u32]);
+            images.push([0.0; 12]);
+
+            if let Some(image) = self.image_instance(&dom.rects[index], style) {
+                indices.push(index);
+                instances.push([IMAGE, 0, 0]);
+                images.push(image);
+            }
+
+            if let Some((range, _)) = self.draws.last_mut() {
+                range.end = indices.len() as u32;
+            }
+        }
+
+        let n = indices.len();
+        self.n = n as u32;
+        if n == 0 {
+            return;
+        }
 
         // Reallocate buffers if needed
         if n > self.size {
             self.rects_buffer = Self::create_rects_buffer(device, n * 2);
             self.styles_buffer = Self::create_styles_buffer(device, n * 2);
-            self.size = dom.rects.len() * 2;
+            self.instances_buffer = Self::create_instances_buffer(device, n * 2);
+            self.images_buffer = Self::create_images_buffer(device, n * 2);
+            self.size = n * 2;
+        }
+        if stops.len() > self.stops_size {
+            self.stops_buffer = Self::create_stops_buffer(device, stops.len() * 2);
+            self.stops_size = stops.len() * 2;
+            self.uniforms_group = Self::create_uniforms_group(
+                device,
+                &self.uniforms_layout,
+                &self.screen_buffer,
+                &self.stops_buffer,
+            );
         }
-        self.n = n as u32;
 
         // Write rects to buffer
-        write_indexed_slice_to_buffer(&dom.rects, &dom.redraw, &self.rects_buffer, queue);
+        write_indexed_slice_to_buffer(&dom.rects, &indices, &self.rects_buffer, queue);
 
         // Write styles to buffer
-        write_indexed_slice_to_buffer(&dom.styles, &dom.redraw, &self.styles_buffer, queue);
+        write_indexed_slice_to_buffer(&dom.styles, &indices, &self.styles_buffer, queue);
+
+        // Write instances, images & gradient stops to buffers
+        queue.write_buffer(&self.instances_buffer, 0, bytemuck::cast_slice(&instances));
+        queue.write_buffer(&self.images_buffer, 0, bytemuck::cast_slice(&images));
+        if !stops.is_empty() {
+            queue.write_buffer(&self.stops_buffer, 0, bytemuck::cast_slice(&stops));
+        }
+    }
+
+    /// Add a decoded image for a source, drawn by the elements with this image source instead
+    /// of the PNG file at this path. Returns whether the image fits in the atlas.
+    pub fn insert_image(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        source: impl Into<String>,
+        image: &Image,
+    ) -> bool {
+        let atlas_size = self.atlas.size();
+        let region = self.atlas.insert(device, queue, source, image);
+        if self.atlas.size() != atlas_size {
+            self.atlas_group = Self::create_atlas_group(device, &self.atlas_layout, &self.atlas);
+        }
+
+        region.is_some()
+    }
+
+    /// Placement of the loaded image of an element in it & in the atlas, and its tint:
+    /// logical offset & size, atlas texture coordinates, RGBA tint
+    fn image_instance(&self, rect: &Rect, style: &ComputedStyle) -> Option<[f32; 12]> {
+        let region = self.atlas.get(style.image.as_deref()?)?;
+        let size = rect.size.map(|s| s as f32);
+        let placement = fit_image(style.image_fit, region.size, size);
+
+        // From fractions of the image to fractions of the atlas
+        let atlas = self.atlas.size() as f32;
+        let u = |f: f32| (region.position[0] as f32 + f * region.size[0] as f32) / atlas;
+        let v = |f: f32| (region.position[1] as f32 + f * region.size[1] as f32) / atlas;
+        let [left, top, right, bottom] = placement.uv;
+        let [x, y, width, height] = placement.rect;
+        let tint = style
+            .image_tint
+            .as_ref()
+            .map_or([1.0; 4], |c| [c.r, c.g, c.b, c.a]);
+
+        Some([
+            x,
+            y,
+            width,
+            height,
+            u(left),
+            v(top),
+            u(right),
+            v(bottom),
+            tint[0],
+            tint[1],
+            tint[2],
+            tint[3],
+        ])
     }
 
     // ************************************************* //
@@ -289,4 +630,88 @@ impl Pipeline {
             mapped_at_creation: false,
         })
     }
+
+    fn create_instances_buffer(device: &Device, size: usize) -> Buffer {
+        device.create_buffer(&BufferDescriptor {
+            label: Some("Instances Buffer"),
+            size: (size * std::mem::size_of::<[u32; 3]>()) as u64,
+            usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    fn create_images_buffer(device: &Device, size: usize) -> Buffer {
+        device.create_buffer(&BufferDescriptor {
+            label: Some("Images Buffer"),
+            size: (size * std::mem::size_of::<[f32; 12]>()) as u64,
+            usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    fn create_stops_buffer(device: &Device, size: usize) -> Buffer {
+        device.create_buffer(&BufferDescriptor {
+            label: Some("Gradient Stops Buffer"),
+            size: (size.max(1) * std::mem::size_of::<[f32; 8]>()) as u64,
+            usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    fn create_uniforms_group(
+        device: &Device,
+        layout: &BindGroupLayout,
+        screen_buffer: &Buffer,
+        stops_buffer: &Buffer,
+    ) -> BindGroup {
+        device.create_bind_group(&BindGroupDescriptor {
+            layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: screen_buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: stops_buffer.as_entire_binding(),
+                },
+            ],
+            label: Some("Uniforms Bind Group"),
+        })
+    }
+
+    fn create_atlas_group(device: &Device, layout: &BindGroupLayout, atlas: &Atlas) -> BindGroup {
+        device.create_bind_group(&BindGroupDescriptor {
+            layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: BindingResource::TextureView(atlas.view()),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: BindingResource::Sampler(atlas.sampler()),
+                },
+            ],
+            label: Some("Atlas Bind Group"),
+        })
+    }
+}
+
+/// Pipeline drawing into a render pass of the host application
+impl Renderer for Pipeline {
+    type Context<'a> = (&'a Device, &'a Queue);
+    type Target<'a> = RenderPass<'a>;
+
+    fn resize(&mut self, (_, queue): (&Device, &Queue), size: [u32; 2], scale: f32) {
+        self.update_screen(queue, size, scale);
+    }
+
+    fn prepare(&mut self, (device, queue): (&Device, &Queue), dom: &DOM) {
+        self.update_elements(device, queue, dom);
+    }
+
+    fn draw(&self, target: &mut RenderPass<'_>) {
+        self.render(target);
+    }
 }
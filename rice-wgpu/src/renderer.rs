@@ -0,0 +1,172 @@
+//! Rice UI embedded in a host application, which owns the device, window & event loop
+
+use rice_dom::DOM;
+use rice_layout::Measure;
+use rice_render::{Image, Renderer};
+use wgpu::*;
+use winit::{
+    dpi::{LogicalPosition, PhysicalPosition},
+    event::{ElementState, MouseButton, MouseScrollDelta, WindowEvent},
+};
+
+use crate::pipeline::Pipeline;
+
+/// Pixels scrolled per line for line-based mouse wheels
+const SCROLL_LINE: f32 = 40.0;
+
+/// DOM & drawing pipeline, driven by the host application:
+///
+/// 1. Forward window events with `handle_window_event`, and redraw when it returns true
+/// 2. Before each frame, lay out the nodes whose layout changed (see `DOM::set_layout`)
+///    and upload the changes of the DOM with `prepare`
+/// 3. Draw the UI in a render pass of the frame with `render`, e.g. over 3D content
+///
+/// The whole UI is drawn in each frame, so the render pass can be cleared beforehand.
+pub struct RiceRenderer {
+    /// DOM of the UI, which can be inspected & updated between frames.
+    /// Nodes marked for relayout or dirty are updated by the next `prepare`.
+    pub dom: DOM,
+    pipeline: Pipeline,
+    /// Glyph metrics used to lay out text
+    measure: Box<dyn Measure>,
+    /// Physical size of the screen
+    size: [u32; 2],
+    /// Logical to physical scaling factor
+    scale: f32,
+    /// Whether the screen changed since the last `prepare`
+    resized: bool,
+}
+
+impl RiceRenderer {
+    /// Create a renderer drawing to textures of the given format, for a screen of the given
+    /// physical size & scale factor. The layout of the DOM must have been computed, with the
+    /// glyph metrics used to lay it out again.
+    pub fn new(
+        device: &Device,
+        format: TextureFormat,
+        mut dom: DOM,
+        measure: impl Measure + 'static,
+        size: [u32; 2],
+        scale: f32,
+    ) -> Self {
+        let pipeline = Pipeline::new(device, size, scale, format, 100);
+        dom.dirty.push(dom.root); // Mark the root node as dirty
+
+        Self {
+            dom,
+            pipeline,
+            measure: Box::new(measure),
+            size,
+            scale,
+            resized: false,
+        }
+    }
+
+    /// Update the physical size & scale factor of the screen
+    pub fn resize(&mut self, size: [u32; 2], scale: f32) {
+        self.size = size;
+        self.scale = scale;
+        self.resized = true;
+        self.dom.dirty.push(self.dom.root);
+    }
+
+    /// Whether the UI changed since the last `prepare`
+    pub fn needs_redraw(&self) -> bool {
+        self.resized || !self.dom.relayout.is_empty() || !self.dom.dirty.is_empty()
+    }
+
+    /// Update the DOM from a window event (resize, mouse moves, clicks & scrolls).
+    /// Returns whether the UI needs to be redrawn.
+    pub fn handle_window_event(&mut self, event: &WindowEvent) -> bool {
+        match event {
+            WindowEvent::Resized(size) => {
+                self.resize([size.width.max(1), size.height.max(1)], self.scale);
+            }
+            WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                self.resize(self.size, *scale_factor as f32);
+            }
+            WindowEvent::CursorMoved { position, .. } => {
+                let position = self.logical(*position);
+                self.dom.handle_mouse_moved([position.x, position.y]);
+            }
+            WindowEvent::CursorLeft { .. } => self.dom.reset_mouse(),
+            WindowEvent::MouseWheel { delta, .. } => {
+                // Positive winit deltas move the content right and down
+                let delta = match delta {
+                    MouseScrollDelta::LineDelta(x, y) => {
+                        [(-x * SCROLL_LINE) as i32, (-y * SCROLL_LINE) as i32]
+                    }
+                    MouseScrollDelta::PixelDelta(position) => {
+                        let position = self.logical(*position);
+                        [-position.x, -position.y]
+                    }
+                };
+                self.dom.handle_mouse_wheel(delta);
+            }
+            WindowEvent::MouseInput {
+                state,
+                button: MouseButton::Left,
+                ..
+            } => {
+                self.dom
+                    .handle_mouse_clicked(*state == ElementState::Pressed);
+            }
+            _ => return false,
+        }
+
+        self.needs_redraw()
+    }
+
+    /// Lay out the nodes marked for relayout, and upload the screen & the elements of the DOM
+    /// to the GPU, if they changed
+    pub fn prepare(&mut self, device: &Device, queue: &Queue) {
+        self.dom.compute_relayout(self.measure.as_ref());
+
+        if self.resized {
+            self.pipeline.resize((device, queue), self.size, self.scale);
+            self.resized = false;
+        }
+
+        // All elements are drawn in each frame
+        if !self.dom.dirty.is_empty() {
+            self.dom.dirty.push(self.dom.root);
+            self.dom.compute_redraw();
+            self.pipeline.prepare((device, queue), &self.dom);
+            self.dom.dirty.clear();
+            self.dom.redraw.clear();
+            self.dom.reload_images = false;
+        }
+    }
+
+    /// Add a decoded image for a source, as `Pipeline::insert_image`
+    pub fn insert_image(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        source: impl Into<String>,
+        image: &Image,
+    ) -> bool {
+        self.dom.dirty.push(self.dom.root);
+        self.pipeline.insert_image(device, queue, source, image)
+    }
+
+    /// Draw the UI in a render pass, over its previous content
+    pub fn render(&self, render_pass: &mut RenderPass<'_>) {
+        self.pipeline.draw(render_pass);
+    }
+
+    /// Draw the UI to a texture view, in a new render pass
+    pub fn render_to_view(
+        &self,
+        encoder: &mut CommandEncoder,
+        view: &TextureView,
+        load: LoadOp<Color>,
+    ) {
+        self.pipeline.render_to_view(encoder, view, load);
+    }
+
+    /// Logical position of a physical position on the screen
+    fn logical(&self, position: PhysicalPosition<f64>) -> LogicalPosition<i32> {
+        position.to_logical(self.scale as f64)
+    }
+}
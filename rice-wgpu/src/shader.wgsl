@@ -9,11 +9,40 @@ struct Screen {
 struct VertexOutput {
     @builtin(position) position: vec4<f32>,
     @location(0) size: vec2<f32>,
-    @location(1) offset: vec2<f32>,
-    @location(2) color: vec4<f32>,
+    @location(1) color: vec4<f32>,
+    // Physical position within the rect, from its top left corner
+    @location(2) local: vec2<f32>,
+    // Physical corner radii: top left, top right, bottom right, bottom left
+    @location(3) radius: vec4<f32>,
+    // Physical border widths: top, right, bottom, left
+    @location(4) border: vec4<f32>,
+    @location(5) border_color: vec4<f32>,
+    // Physical blur radius of the shadow
+    @location(6) blur: f32,
+    // Whether the element (0), its shadow (1) or its image (2) is drawn
+    @location(7) @interpolate(flat) layer: u32,
+    // Gradient kind (0: none, 1: linear, 2: radial) & parameters
+    @location(8) @interpolate(flat) gradient: vec4<f32>,
+    // First gradient stop & amount of stops
+    @location(9) @interpolate(flat) stops: vec2<u32>,
+    // Position in the atlas texture
+    @location(10) uv: vec2<f32>,
+}
+
+// Color at a position along a gradient
+struct Stop {
+    color: vec4<f32>,
+    position: f32,
 }
 
 @group(0) @binding(0) var<uniform> screen: Screen;
+@group(0) @binding(1) var<storage, read> stops: array<Stop>;
+@group(1) @binding(0) var atlas: texture_2d<f32>;
+@group(1) @binding(1) var atlas_sampler: sampler;
+
+// Layers of the instances drawn beneath & above the element
+const SHADOW: u32 = 1u;
+const IMAGE: u32 = 2u;
 
 @vertex
 fn vertex_shader(
@@ -21,15 +50,65 @@ fn vertex_shader(
     @location(1) size: vec2<f32>,
     @location(2) offset: vec2<f32>,
     @location(3) color: vec4<f32>,
+    @location(4) radius: vec4<f32>,
+    @location(5) border: vec4<f32>,
+    @location(6) border_color: vec4<f32>,
+    // Offset, blur radius & spread
+    @location(7) shadow: vec4<f32>,
+    @location(8) shadow_color: vec4<f32>,
+    @location(9) layer: u32,
+    // Linear: angle in radians, radial: center as fractions of the size & logical radius
+    @location(10) gradient: vec4<f32>,
+    @location(11) gradient_stops: vec2<u32>,
+    // Logical offset & size of the image within the element
+    @location(12) image_rect: vec4<f32>,
+    // Drawn part of the atlas: left, top, right, bottom
+    @location(13) image_uv: vec4<f32>,
+    @location(14) tint: vec4<f32>,
 ) -> VertexOutput {
     var out: VertexOutput;
 
     // Pass physical size & position to fragment shader
     out.size = size * screen.scale;
-    out.offset = offset * screen.scale;
     out.color = color;
+    out.local = (vertex + 1) / 2.0 * out.size;
+    out.radius = radius * screen.scale;
+    out.border = border * screen.scale;
+    out.border_color = border_color;
+    out.blur = 0.0;
+    out.layer = layer;
+    out.gradient = gradient;
+    out.stops = gradient_stops;
+    out.uv = vec2<f32>(0.0);
+
+    // Logical rectangle covered by the instance
+    var quad_size = size;
+    var quad_offset = offset;
+
+    if layer == SHADOW {
+        // Box grown by the spread, with the blur fading out over 3 standard deviations
+        let spread = shadow.w;
+        let padding = spread + 1.5 * shadow.z + 1.0;
+        quad_size = size + 2.0 * padding;
+        quad_offset = offset + shadow.xy - padding;
+
+        out.size = max(size + 2.0 * spread, vec2<f32>(0.0)) * screen.scale;
+        out.color = shadow_color;
+        out.local = ((vertex + 1) / 2.0 * quad_size - padding + spread) * screen.scale;
+        out.radius = select(out.radius, max(out.radius + spread * screen.scale, vec4<f32>(0.0)), radius > vec4<f32>(0.0));
+        out.blur = shadow.z * screen.scale;
+    } else if layer == IMAGE {
+        // Image placed within the element, clipped by its corners
+        let corner = (vertex + 1) / 2.0;
+        quad_size = image_rect.zw;
+        quad_offset = offset + image_rect.xy;
 
-    var position: vec2<f32> = ((vertex + 1) / 2.0 * size + offset) * screen.scale / screen.size * 2.0 - 1.0;
+        out.color = tint;
+        out.local = (image_rect.xy + corner * image_rect.zw) * screen.scale;
+        out.uv = mix(image_uv.xy, image_uv.zw, corner);
+    }
+
+    var position: vec2<f32> = ((vertex + 1) / 2.0 * quad_size + quad_offset) * screen.scale / screen.size * 2.0 - 1.0;
     position.y *= -1.0; // Invert Y axis
 
     out.position = vec4<f32>(
@@ -41,10 +120,89 @@ fn vertex_shader(
     return out;
 }
 
+// Signed distance from a point to a rounded box centered on the origin
+fn rounded_box(p: vec2<f32>, half: vec2<f32>, radius: vec4<f32>) -> f32 {
+    // Radius of the corner in the quadrant of the point
+    let sides = select(radius.xw, radius.yz, p.x > 0.0); // Top & bottom
+    let r = min(select(sides.x, sides.y, p.y > 0.0), min(half.x, half.y));
+
+    let q = abs(p) - half + r;
+    return length(max(q, vec2<f32>(0.0))) + min(max(q.x, q.y), 0.0) - r;
+}
+
+// Approximation of the error function, with a max error of 5e-4
+fn erf(x: f32) -> f32 {
+    let a = abs(x);
+    let t = 1.0 + (0.278393 + (0.230389 + 0.078108 * a * a) * a) * a;
+    let r = t * t;
+    return sign(x) * (1.0 - 1.0 / (r * r));
+}
+
+// Background color at a physical position within the element, relative to its center
+fn background(in: VertexOutput, p: vec2<f32>) -> vec4<f32> {
+    if in.stops.y == 0u {
+        return in.color;
+    }
+
+    // Position along the gradient
+    var t: f32;
+    if in.gradient.x == 1.0 {
+        // Gradient line through the center, long enough for the corners to reach its ends
+        let direction = vec2<f32>(sin(in.gradient.y), -cos(in.gradient.y));
+        let length = abs(in.size.x * direction.x) + abs(in.size.y * direction.y);
+        t = dot(p, direction) / length + 0.5;
+    } else {
+        let center = in.gradient.yz * in.size;
+        t = distance(in.local, center) / (in.gradient.w * screen.scale);
+    }
+
+    var color = stops[in.stops.x].color;
+    for (var i = 1u; i < in.stops.y; i++) {
+        let start = stops[in.stops.x + i - 1u];
+        let end = stops[in.stops.x + i];
+        if t > start.position {
+            let f = clamp((t - start.position) / max(end.position - start.position, 1e-6), 0.0, 1.0);
+            color = mix(start.color, end.color, f);
+        }
+    }
+    return color;
+}
 
 @fragment
 fn fragment_shader(
     in: VertexOutput,
 ) -> @location(0) vec4<f32> {
-    return in.color;
+    let half = in.size / 2.0;
+    let p = in.local - half;
+
+    // Rounded box convolved with a gaussian, approximated from its signed distance
+    if in.layer == SHADOW {
+        let sigma = max(in.blur / 2.0, 0.5);
+        let d = rounded_box(p, half, in.radius);
+        return in.color * (0.5 - 0.5 * erf(d / (sigma * sqrt(2.0))));
+    }
+
+    // Tinted image, with premultiplied alpha
+    if in.layer == IMAGE {
+        let coverage = clamp(0.5 - rounded_box(p, half, in.radius), 0.0, 1.0);
+        let texel = textureSampleLevel(atlas, atlas_sampler, in.uv, 0.0) * in.color;
+        return vec4<f32>(texel.rgb * texel.a, texel.a) * coverage;
+    }
+
+    // Anti-aliased coverage of the pixel
+    let coverage = clamp(0.5 - rounded_box(p, half, in.radius), 0.0, 1.0);
+
+    // Box inside the borders, with corner radii reduced by the widths of the adjacent sides
+    let b = in.border;
+    let inner_half = max(half - vec2<f32>(b.w + b.y, b.x + b.z) / 2.0, vec2<f32>(0.0));
+    let inner_center = vec2<f32>(b.w - b.y, b.x - b.z) / 2.0;
+    let inner_radius = max(
+        in.radius - vec4<f32>(max(b.w, b.x), max(b.x, b.y), max(b.y, b.z), max(b.z, b.w)),
+        vec4<f32>(0.0),
+    );
+    let inner = clamp(0.5 - rounded_box(p - inner_center, inner_half, inner_radius), 0.0, 1.0);
+
+    let bg = background(in, p);
+    let color = select(bg, mix(in.border_color, bg, inner), any(b > vec4<f32>(0.0)));
+    return color * coverage;
 }
@@ -2,7 +2,7 @@
 
 use std::num::NonZeroU64;
 
-use rice_dom::{Color, ComputedStyle};
+use rice_dom::{Color, ComputedStyle, GradientKind};
 use rice_layout::Rect;
 use wgpu::*;
 
@@ -37,11 +37,32 @@ impl WriteBuffer for Color {
 }
 
 impl WriteBuffer for ComputedStyle {
-    const SIZE: usize = Color::SIZE;
+    const SIZE: usize = 7 * std::mem::size_of::<[f32; 4]>();
 
     fn write_buffer(&self, buffer: &mut [u8]) {
-        self.background_color
-            .write_buffer(&mut buffer[..Color::SIZE]);
+        let size = std::mem::size_of::<[f32; 4]>();
+        self.background_color.write_buffer(&mut buffer[..size]);
+        buffer[size..2 * size].copy_from_slice(bytemuck::bytes_of(&self.border_radius));
+        buffer[2 * size..3 * size].copy_from_slice(bytemuck::bytes_of(&self.border_width));
+        self.border_color
+            .write_buffer(&mut buffer[3 * size..4 * size]);
+        buffer[4 * size..5 * size].copy_from_slice(bytemuck::bytes_of(&[
+            self.shadow.offset[0],
+            self.shadow.offset[1],
+            self.shadow.blur,
+            self.shadow.spread,
+        ]));
+        self.shadow
+            .color
+            .write_buffer(&mut buffer[5 * size..6 * size]);
+
+        // Gradient kind (none, linear or radial) & parameters
+        let gradient = match self.background_gradient.as_ref().map(|g| g.kind) {
+            None => [0.0; 4],
+            Some(GradientKind::Linear { angle }) => [1.0, angle.to_radians(), 0.0, 0.0],
+            Some(GradientKind::Radial { center, radius }) => [2.0, center[0], center[1], radius],
+        };
+        buffer[6 * size..7 * size].copy_from_slice(bytemuck::bytes_of(&gradient));
     }
 }
 